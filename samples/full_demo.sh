@@ -77,8 +77,8 @@ else
   echo "You have declined to provide your gender identity."
 fi
 
-# We can test to see if variables are set, even for repeated arguments.
-if [ -n "$DEMO_NICKNAMES" ]; then
+# Repeated arguments always get a count, so we can check whether any values were given.
+if [ "$DEMO_NICKNAMES" -gt 0 ]; then
   echo ""
   echo "You have $DEMO_NICKNAMES nickname(s):";
   for (( i=0; i<$DEMO_NICKNAMES; i++ )); do
@@ -2,8 +2,13 @@ use std::collections::VecDeque;
 
 use super::argument_common::ArgumentCommon;
 use super::argument_common::MatchResult;
+use super::errors::ArgError;
 use super::errors::OptionExt;
 use super::errors::USER_ERROR;
+use super::output_argument;
+use super::output_array;
+use super::output_indexed;
+use super::Settings;
 
 
 pub enum HelpDetailSection {
@@ -16,27 +21,120 @@ pub trait Argument {
   /// Provides a terse representation of the argument, suitable for debugging.
   fn get_debug_info(&self) -> String;
 
+  /// Describes the argument as a JSON object, for `--export-spec`.
+  fn to_spec_json(&self) -> String;
+
   /// Gets the ArgumentCommon pieces of the Argument.
   fn get_common(&self) -> &ArgumentCommon;
 
+  /// Gets the ArgumentCommon pieces of the Argument for modification after it has been defined.
+  fn get_common_mut(&mut self) -> &mut ArgumentCommon;
+
   /// Attempts to consume the provided argument. 
   ///
-  /// Return value is None if the argument couldn't be consumed, Some(value) if it could. This
-  /// may or may not remove additional items from the `other_args` queue.
-  fn consume(&self, arg: Option<String>, other_args: &mut VecDeque<String>) -> Option<String>;
+  /// Return value is None if the argument couldn't be consumed, Some(values) if it could. There is
+  /// usually a single value, but `--split-on` can turn one into several. This may or may not
+  /// remove additional items from the `other_args` queue. Values that can't be parsed are an error.
+  fn consume(&self, settings: &Settings, arg: Option<String>, other_args: &mut VecDeque<String>) -> Result<Option<Vec<String>>, ArgError>;
 
+  #[allow(clippy::needless_return)]
   fn consume_with_parser(
       &self,
+      settings: &Settings,
       arg: Option<String>,
       other_args: &mut VecDeque<String>,
-      parser: fn(&String, &String) -> String) -> Option<String> {
-    match self.get_common().check_flag_match(arg) {
-      MatchResult::NoMatch => None,
-      MatchResult::MatchWithValue(_flag, value) => Some(parser(self.get_name(), &value)),
-      MatchResult::MatchWithoutValue => Some(parser(
-          self.get_name(),
-          &other_args.pop_front()
-            .unwrap_or_error(USER_ERROR, format!("No value provided for argument {}", self.get_name()))))
+      parser: &dyn Fn(&String, &String) -> Result<String, ArgError>) -> Result<Option<Vec<String>>, ArgError> {
+    let value = match self.get_common().check_flag_match(arg) {
+      MatchResult::NoMatch => return Ok(None),
+      MatchResult::MatchWithValue(_flag, value) => self.get_common().resolve_stdin(settings, value)?,
+      MatchResult::MatchWithoutValue => self.get_common().resolve_stdin(settings, other_args.pop_front()
+          .ok_or_error(USER_ERROR, settings.messages.get("no_value", &[("name", self.get_name())]))?)?,
+    };
+
+    let values = self.get_common()
+        .split_value(value)
+        .iter()
+        .map(|value| parser(self.get_name(), value))
+        .collect::<Result<Vec<String>, ArgError>>()?;
+    return Ok(Some(values));
+  }
+
+  /// Whether a flag for this argument is followed by a value. Arguments that don't take a value can
+  /// be bundled with other short flags, as in `-abc`.
+  fn takes_value(&self) -> bool {
+    true
+  }
+
+  /// Checks that the `--default` value, if any, is valid for this argument, failing with a
+  /// definition error if it is not.
+  fn check_default(&self, _settings: &Settings) -> Result<(), ArgError> {
+    Ok(())
+  }
+
+  /// Checks the `--default` value just before it is used, for checks that depend on the system
+  /// rather than on the definitions, failing with a user error if it is not valid.
+  fn check_used_default(&self, _settings: &Settings, _default: &str) -> Result<(), ArgError> {
+    Ok(())
+  }
+
+  /// Gets the names of the variables this argument writes besides its own, such as the raw values of
+  /// a choice with `--emit-raw`.
+  fn get_extra_names(&self) -> Vec<String> {
+    Vec::new()
+  }
+
+  /// Gets the variables written for the values collected for this argument, by name. Most arguments
+  /// write their values as they are, to a single variable.
+  fn output_variables(&self, _settings: &Settings, values: &[String]) -> Vec<(String, Vec<String>)> {
+    vec![(self.get_name().clone(), values.to_vec())]
+  }
+
+  /// Writes the shell assignments for the values collected for this argument.
+  fn output_values(&self, settings: &Settings, values: &[String]) {
+    for (name, values) in self.output_variables(settings, values) {
+      self.output_values_as(settings, &name, &values);
+    }
+  }
+
+  /// Writes values the way this argument writes its own, but to another variable name. This lets an
+  /// argument write more than one variable.
+  fn output_values_as(&self, settings: &Settings, name: &String, values: &[String]) {
+    if self.get_join().is_some() {
+      output_argument(settings, name, values.join(self.get_join().as_ref().unwrap()), self.is_secret());
+    } else if self.is_repeated() && (settings.as_array || self.is_as_array() || settings.shell.has_native_lists()) {
+      output_array(settings, name, values, self.is_secret());
+    } else if self.is_repeated() {
+      output_indexed(settings, name, values, self.is_secret());
+    } else {
+      output_argument(settings, name, values.first().unwrap(), self.is_secret());
+    }
+  }
+
+  /// Whether the values for this argument are written as a list (an array, or a count and indexed
+  /// variables) rather than as a single variable.
+  fn writes_list(&self) -> bool {
+    self.is_repeated() && self.get_join().is_none()
+  }
+
+  /// Converts the `--default` value into the value that is written out, the way a value from the
+  /// user would be converted. The default has already been checked by `check_default`.
+  fn default_output_value(&self, _settings: &Settings, default: &str) -> String {
+    default.to_string()
+  }
+
+  /// Writes the shell assignments for the default value, used when no values were collected. A
+  /// repeated argument gets the default as its only value, so its count is always set.
+  fn output_default(&self, settings: &Settings, default: &str) {
+    self.output_values(settings, &[self.default_output_value(settings, default)]);
+  }
+
+  /// Writes the shell assignment for an argument that got no value and has no default, used with
+  /// `--declare-all` so that the variable always exists.
+  fn output_missing(&self, settings: &Settings) {
+    if self.is_repeated() {
+      self.output_values(settings, &[]);
+    } else {
+      output_argument(settings, self.get_name(), "", self.is_secret());
     }
   }
 
@@ -44,12 +142,27 @@ pub trait Argument {
     vec![HelpDetailSection::Text(self.get_description().clone().unwrap_or(String::from("No details available.")))]
   }
 
+  /// Values offered by shell completion after one of this argument's flags. Arguments that take
+  /// free-form values offer nothing.
+  fn get_completion_values(&self) -> Vec<String> {
+    Vec::new()
+  }
+
+  /// Gets the flags shown in help text. An argument with an ordinal is also listed by its position,
+  /// which is the only entry for arguments that have no flags at all.
+  #[allow(clippy::needless_return)]
   fn get_help_flags(&self) -> Vec<String> {
-    self.get_common()
+    let mut flags: Vec<String> = self.get_common()
         .get_all_flags()
         .iter()
         .map(|flag| format!("{} <{}>", flag, self.get_name().to_lowercase()))
-        .collect()
+        .collect();
+
+    if self.get_ordinal().is_some() {
+      flags.push(format!("<{}> (positional, position {})", self.get_name().to_lowercase(), self.get_ordinal().unwrap()));
+    }
+
+    return flags;
   }
 
   fn get_help_default(&self) -> Option<String> {
@@ -93,5 +206,91 @@ pub trait Argument {
   fn is_catch_all(&self) -> bool {
     self.get_common().get_catch_all()
   }
+
+  fn is_as_array(&self) -> bool {
+    self.get_common().get_as_array()
+  }
+
+  fn get_min_count(&self) -> &Option<usize> {
+    self.get_common().get_min_count()
+  }
+
+  fn get_max_count(&self) -> &Option<usize> {
+    self.get_common().get_max_count()
+  }
+
+  fn get_env(&self) -> &Option<String> {
+    self.get_common().get_env()
+  }
+
+  fn get_split_on(&self) -> &Option<String> {
+    self.get_common().get_split_on()
+  }
+
+  fn get_join(&self) -> &Option<String> {
+    self.get_common().get_join()
+  }
+
+  fn get_mutex_groups(&self) -> &Vec<String> {
+    self.get_common().get_mutex_groups()
+  }
+
+  fn get_one_of_groups(&self) -> &Vec<String> {
+    self.get_common().get_one_of_groups()
+  }
+
+  fn get_conflicts_with(&self) -> &Vec<String> {
+    self.get_common().get_conflicts_with()
+  }
+
+  /// Checks whether a reference from another argument (such as `--conflicts-with`) means this
+  /// argument. References can use either the argument name or one of its flags, with or without the
+  /// leading dashes.
+  #[allow(clippy::needless_return)]
+  fn is_referenced_by(&self, reference: &str) -> bool {
+    let bare = reference.trim_start_matches('-');
+    return self.get_name() == reference
+        || self.get_common().get_all_flags().iter().any(|flag| flag == reference || flag.trim_start_matches('-') == bare);
+  }
+
+  /// Gets the flag used to refer to this argument in messages, falling back to its name when the
+  /// argument has no flags.
+  fn get_display_flag(&self) -> String {
+    self.get_common().get_all_flags().first().unwrap_or(self.get_name()).clone()
+  }
 }
 
+
+#[cfg(test)]
+mod tests {
+  use super::super::test_support::eval_in;
+  use super::super::test_support::run;
+
+  const VALUES: [&str; 4] = ["a b", "it's", "$HOME", ""];
+
+  #[allow(clippy::needless_return)]
+  fn array_in_bash(definition: &[&str]) -> String {
+    let mut args = definition.to_vec();
+    args.push("--");
+    for value in VALUES {
+      args.extend(["--f", value]);
+    }
+
+    let (output, exit_code) = run(&args);
+    assert_eq!(exit_code, 0);
+    return eval_in("bash", &output, "echo \"${#F[@]}\"; printf '<%s>' \"${F[@]}\"");
+  }
+
+  #[test]
+  fn as_array_gives_a_bash_array() {
+    let expected = "4\n<a b><it's><$HOME><>";
+    assert_eq!(array_in_bash(&["--string", "f", "--repeated", "--as-array"]), expected);
+    assert_eq!(array_in_bash(&["--as-array", "--string", "f", "--repeated"]), expected);
+  }
+
+  #[test]
+  fn repeated_arguments_are_indexed_by_default() {
+    let (output, _) = run(&["--string", "f", "--repeated", "--", "--f", "x", "--f", "y"]);
+    assert_eq!(eval_in("sh", &output, "printf '%s|%s|%s' \"$F\" \"$F_0\" \"$F_1\""), "2|x|y");
+  }
+}
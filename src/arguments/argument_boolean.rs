@@ -1,36 +1,80 @@
 use std::collections::VecDeque;
 
 use super::argument::Argument;
+use super::argument::HelpDetailSection;
 use super::argument_common::ArgumentCommon;
 use super::argument_common::ArgumentCommonBuilder;
 use super::argument_common::MatchResult;
+use super::errors::ArgError;
 use super::errors::error;
 use super::errors::OptionExt;
 use super::errors::DEFINITION_ERROR;
 use super::errors::USER_ERROR;
+use super::json::json_string;
+use super::json::json_string_array;
+use super::output_argument;
+use super::Settings;
 
 pub struct BooleanArgument {
   common: ArgumentCommon,
   negative_flags: Vec<String>,
+  true_value: String,
+  false_value: String,
+  true_tokens: Vec<String>,
+  false_tokens: Vec<String>,
+  tri_state: bool,
 }
 
+/// The values users can type for true and false when no tokens are configured.
+const TRUE_TOKENS: [&str; 4] = ["true", "yes", "on", "1"];
+const FALSE_TOKENS: [&str; 4] = ["false", "no", "off", "0"];
+
 impl BooleanArgument {
-  pub fn new(args: &mut VecDeque<String>) -> Self {
+  #[allow(clippy::needless_return, clippy::redundant_field_names, clippy::unnecessary_unwrap)]
+  pub fn new(args: &mut VecDeque<String>) -> Result<Self, ArgError> {
     let mut common = ArgumentCommon::new_builder();
     let mut negative_flags = Vec::new();
+    let mut true_value = String::from("true");
+    let mut false_value = String::from("false");
+    let mut auto_negate = false;
+    let mut tri_state = false;
+    let mut true_tokens = Vec::new();
+    let mut false_tokens = Vec::new();
 
     loop {
-      match common.parse_arguments(args).as_deref() {
+      match common.parse_arguments(args)?.as_deref() {
         None => {
           break;
         }
         Some("--negative-flag") | Some("--negative") | Some("--neg") => {
           let flag = args.pop_front()
-                .unwrap_or_error(DEFINITION_ERROR, String::from("flag must be provided after --negative-flag"));
+                .ok_or_error(DEFINITION_ERROR, String::from("flag must be provided after --negative-flag"))?;
 
           common.add_flag(flag.to_string());
           negative_flags.push(flag.to_string());
         }
+        Some("--auto-negate") => {
+          auto_negate = true;
+        }
+        Some("--tri-state") => {
+          tri_state = true;
+        }
+        Some("--true-value") => {
+          true_value = args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("value must be provided after --true-value"))?;
+        }
+        Some("--false-value") => {
+          false_value = args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("value must be provided after --false-value"))?;
+        }
+        Some("--true-token") => {
+          true_tokens.push(args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("token must be provided after --true-token"))?);
+        }
+        Some("--false-token") => {
+          false_tokens.push(args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("token must be provided after --false-token"))?);
+        }
         Some(other) => {
           args.push_front(other.to_string());
           break;
@@ -38,22 +82,97 @@ impl BooleanArgument {
       }
     }
 
-    let common = common.build();
+    if auto_negate {
+      let positive_flags = common.get_all_flags().iter()
+          .filter(|flag| flag.starts_with("--") && !negative_flags.contains(flag))
+          .cloned()
+          .collect::<Vec<String>>();
+
+      for flag in positive_flags {
+        let negated = format!("--no-{}", &flag[2..]);
+        if !common.get_all_flags().contains(&negated) {
+          common.add_flag(negated.clone());
+          negative_flags.push(negated);
+        }
+      }
+    }
+
+    let common = common.build()?;
 
     if common.get_repeated() {
-      error(DEFINITION_ERROR, format!("Boolean argument {} can not be repeated", common.get_name()));
+      return Err(error(DEFINITION_ERROR, format!("Boolean argument {} can not be repeated", common.get_name())));
 
     } else if common.get_catch_all() {
-      error(DEFINITION_ERROR, format!("Boolean argument {} can not be catch-all", common.get_name()));
+      return Err(error(DEFINITION_ERROR, format!("Boolean argument {} can not be catch-all", common.get_name())));
 
     } else if common.get_ordinal().is_some() {
-      error(DEFINITION_ERROR, format!("Boolean argument {} can not be ordinal", common.get_name()));
+      return Err(error(DEFINITION_ERROR, format!("Boolean argument {} can not be ordinal", common.get_name())));
+
+    } else if true_value == false_value {
+      return Err(error(DEFINITION_ERROR, format!("Boolean argument {} has the same true and false value '{true_value}'", common.get_name())));
+    }
+
+    if true_tokens.is_empty() {
+      true_tokens = TRUE_TOKENS.map(String::from).to_vec();
+    }
+    if false_tokens.is_empty() {
+      false_tokens = FALSE_TOKENS.map(String::from).to_vec();
+    }
+
+    let shared_token = true_tokens.iter().find(|token| false_tokens.iter().any(|other| other.eq_ignore_ascii_case(token)));
+    if shared_token.is_some() {
+      return Err(error(DEFINITION_ERROR, format!(
+          "Boolean argument {} uses '{}' as both a true and a false token",
+          common.get_name(),
+          shared_token.unwrap())));
     }
 
-    return BooleanArgument {
+    return Ok(BooleanArgument {
       common: common,
       negative_flags: negative_flags,
-    };
+      true_value: true_value,
+      false_value: false_value,
+      true_tokens: true_tokens,
+      false_tokens: false_tokens,
+      tri_state: tri_state,
+    });
+  }
+
+  /// Gets the output word for a boolean value.
+  #[allow(clippy::needless_return)]
+  fn output_word(&self, value: bool) -> String {
+    if value {
+      return self.true_value.clone();
+    } else {
+      return self.false_value.clone();
+    }
+  }
+
+  /// Reads a boolean value given by the user, in any case. Unless `--true-token` or `--false-token`
+  /// are used, the usual command line words "yes", "no", "on", "off", "1" and "0" are accepted
+  /// besides "true" and "false".
+  #[allow(clippy::needless_return)]
+  fn parse_token(&self, value: &str) -> Option<bool> {
+    if self.true_tokens.iter().any(|token| token.eq_ignore_ascii_case(value)) {
+      return Some(true);
+    } else if self.false_tokens.iter().any(|token| token.eq_ignore_ascii_case(value)) {
+      return Some(false);
+    }
+    return None;
+  }
+
+  /// Converts the `--default` value to the word that is written out. A default that is one of the
+  /// accepted tokens is written using the configured output words.
+  fn default_word(&self, default: &str) -> String {
+    match self.parse_token(default) {
+      Some(value) => self.output_word(value),
+      None => default.to_string(),
+    }
+  }
+
+  /// Whether the accepted tokens were changed with `--true-token` or `--false-token`.
+  fn has_custom_tokens(&self) -> bool {
+    self.true_tokens != TRUE_TOKENS || self.false_tokens != FALSE_TOKENS
   }
 }
 
@@ -63,55 +182,190 @@ impl Argument for BooleanArgument {
         self.common
           .get_all_flags()
           .iter()
-          .map(|flag| format!("{flag}[=<true|false>]"))
+          .filter(|flag| !self.negative_flags.contains(flag))
+          .map(|flag| format!("{flag}[=<{}|{}>]", self.true_tokens[0], self.false_tokens[0]))
           .collect::<Vec<String>>(),
         self.negative_flags
           .iter()
-          .map(|flag| format!("{flag}"))
+          .map(|flag| flag.to_string())
           .collect::<Vec<String>>()
     ].concat()
   }
 
   fn get_help_default(&self) -> Option<String> {
-    Some(String::from("When this option is not provided it will default to false. ") +
-         &String::from("If provided without a value it will be set to true."))
+    if self.tri_state && self.get_default().is_none() {
+      return Some(format!(
+          "When this option is not provided it is left unset. If provided without a value it will be set to {}.",
+          self.true_value));
+    }
+
+    let default = match self.get_default() {
+      Some(default) => self.default_word(default),
+      None => self.false_value.clone(),
+    };
+
+    Some(format!(
+        "When this option is not provided it will default to {}. If provided without a value it will be set to {}.",
+        default,
+        self.true_value))
+  }
+
+  fn get_help_details(&self) -> Vec<HelpDetailSection> {
+    let mut lines = vec![
+        HelpDetailSection::Text(self.get_description().clone().unwrap_or(String::from("No details available."))),
+    ];
+
+    if self.has_custom_tokens() {
+      lines.push(HelpDetailSection::Text(format!(
+          "The value can be {} for true, or {} for false.",
+          self.true_tokens.join(", "),
+          self.false_tokens.join(", "))));
+    }
+
+    lines
   }
 
   fn get_common(&self) -> &ArgumentCommon {
     &self.common
   }
 
+  fn get_common_mut(&mut self) -> &mut ArgumentCommon {
+    &mut self.common
+  }
+
+  fn takes_value(&self) -> bool {
+    false
+  }
+
+  #[allow(clippy::needless_return)]
   fn get_debug_info(&self) -> String {
-    return format!("type: Boolean; {}", self.common.get_debug_info());
+    let mut description = format!("type: Boolean; {}", self.common.get_debug_info());
+    if self.true_value != "true" || self.false_value != "false" {
+      description.push_str(&format!("; values: {}/{}", self.true_value, self.false_value));
+    }
+    if self.tri_state {
+      description.push_str("; tri-state");
+    }
+    if self.has_custom_tokens() {
+      description.push_str(&format!(
+          "; true tokens: {}; false tokens: {}",
+          self.true_tokens.join(", "),
+          self.false_tokens.join(", ")));
+    }
+    return description;
+  }
+
+  fn to_spec_json(&self) -> String {
+    self.common.to_spec_json("boolean", vec![
+        ("negative_flags", json_string_array(&self.negative_flags)),
+        ("true_value", json_string(&self.true_value)),
+        ("false_value", json_string(&self.false_value)),
+        ("true_tokens", json_string_array(&self.true_tokens)),
+        ("false_tokens", json_string_array(&self.false_tokens)),
+        ("tri_state", self.tri_state.to_string()),
+    ])
+  }
+
+  #[allow(clippy::needless_return)]
+  fn check_default(&self, _settings: &Settings) -> Result<(), ArgError> {
+    match self.get_default() {
+      Some(default) if self.parse_token(default).is_none() && default != &self.true_value && default != &self.false_value => {
+        return Err(error(DEFINITION_ERROR, format!("Non-boolean default '{default}' provided for argument {}", self.get_name())));
+      }
+      _ => {}
+    }
+    return Ok(());
+  }
+
+  fn default_output_value(&self, _settings: &Settings, default: &str) -> String {
+    self.default_word(default)
   }
 
-  fn consume(&self, arg: Option<String>, _other_args: &mut VecDeque<String>) -> Option<String> {
+  /// A boolean that was never given is false, as its help text says. A tri-state boolean is empty
+  /// instead, so that it can be told apart from one that was set to false.
+  fn output_missing(&self, settings: &Settings) {
+    if self.tri_state {
+      output_argument(settings, self.get_name(), "", self.is_secret());
+    } else {
+      output_argument(settings, self.get_name(), self.output_word(false), self.is_secret());
+    }
+  }
+
+  #[allow(clippy::needless_return)]
+  fn consume(&self, settings: &Settings, arg: Option<String>, _other_args: &mut VecDeque<String>) -> Result<Option<Vec<String>>, ArgError> {
     match self.common.check_flag_match(arg.clone()) {
       MatchResult::NoMatch => {}
       MatchResult::MatchWithoutValue => {
-        if self.negative_flags.contains(&arg.unwrap()) {
-          return Some(String::from("false"));
-        } else {
-          return Some(String::from("true"));
-        }
+        return Ok(Some(vec![self.output_word(!self.negative_flags.contains(&arg.unwrap()))]));
       }
       MatchResult::MatchWithValue(flag, value) => {
+        // `--no-color=false` is a double negative, so it is rejected rather than inverted.
         if self.negative_flags.contains(&flag) {
-          error(USER_ERROR, format!("Boolean argument {} does not support '{flag}=<value>' syntax", self.get_name()));
+          return Err(error(USER_ERROR, settings.messages.get("negative_flag_value", &[("name", self.get_name()), ("flag", &flag)])));
         }
 
-        return Some(value
-          .parse::<bool>()
-          .unwrap_or_error(USER_ERROR, format!("Non-boolean value '{value}' provided for argument {}", self.get_name()))
-          .to_string());
+        return Ok(Some(vec![self.output_word(self.parse_token(&value)
+          .ok_or_error(USER_ERROR, settings.messages.get("invalid_boolean", &[("value", &value), ("name", self.get_name())]))?)]));
        }
     };
 
     if self.negative_flags.contains(&arg.unwrap()) {
-      return Some(String::from("false"));
+      return Ok(Some(vec![self.false_value.clone()]));
     }
 
-    return None
+    return Ok(None)
   }
 }
 
+
+#[cfg(test)]
+mod tests {
+  use std::collections::VecDeque;
+
+  use super::super::argument::Argument;
+  use super::super::test_support::parse_values;
+  use super::super::test_support::strings;
+  use super::super::ArgError;
+  use super::BooleanArgument;
+
+  const COLOR: [&str; 3] = ["--boolean", "color", "--auto-negate"];
+
+  #[test]
+  fn negative_flags_reject_values() {
+    for input in ["--no-color=false", "--no-color=true", "--no-color=", "--no-color=no"] {
+      assert_eq!(
+          parse_values(&COLOR, &[input]),
+          Err(ArgError::User(String::from("Boolean argument COLOR does not support '--no-color=<value>' syntax"))));
+    }
+
+    let defined = ["--boolean", "happy", "--negative-flag", "--sad"];
+    assert_eq!(
+        parse_values(&defined, &["--sad=false"]),
+        Err(ArgError::User(String::from("Boolean argument HAPPY does not support '--sad=<value>' syntax"))));
+  }
+
+  #[test]
+  fn bare_negative_and_positive_values() {
+    assert_eq!(parse_values(&COLOR, &["--no-color"]).unwrap()["COLOR"], ["false"]);
+    assert_eq!(parse_values(&COLOR, &["--color"]).unwrap()["COLOR"], ["true"]);
+    assert_eq!(parse_values(&COLOR, &["--color=false"]).unwrap()["COLOR"], ["false"]);
+    assert_eq!(parse_values(&COLOR, &["--color=true"]).unwrap()["COLOR"], ["true"]);
+  }
+
+  #[test]
+  fn help_names_the_configured_default() {
+    let help_default = |definition: &[&str]| {
+      BooleanArgument::new(&mut VecDeque::from(strings(definition))).unwrap().get_help_default().unwrap()
+    };
+
+    assert_eq!(
+        help_default(&["verbose", "--default", "yes", "--true-value", "1", "--false-value", "0"]),
+        "When this option is not provided it will default to 1. If provided without a value it will be set to 1.");
+    assert_eq!(
+        help_default(&["verbose", "--default", "off"]),
+        "When this option is not provided it will default to false. If provided without a value it will be set to true.");
+    assert_eq!(
+        help_default(&["verbose"]),
+        "When this option is not provided it will default to false. If provided without a value it will be set to true.");
+  }
+}
@@ -0,0 +1,159 @@
+use std::collections::VecDeque;
+
+use super::argument::Argument;
+use super::argument::HelpDetailSection;
+use super::argument_common::ArgumentCommon;
+use super::argument_common::ArgumentCommonBuilder;
+use super::errors::ArgError;
+use super::errors::OptionExt;
+use super::errors::DEFINITION_ERROR;
+use super::errors::USER_ERROR;
+use super::Settings;
+
+const SUFFIXES: [char; 5] = ['K', 'M', 'G', 'T', 'P'];
+
+pub struct BytesArgument {
+  common: ArgumentCommon,
+  si: bool,
+}
+
+impl BytesArgument {
+  #[allow(clippy::needless_return, clippy::redundant_field_names)]
+  pub fn new(args: &mut VecDeque<String>) -> Result<Self, ArgError> {
+    let mut common = ArgumentCommon::new_builder();
+    let mut si = false;
+
+    loop {
+      match common.parse_arguments(args)?.as_deref() {
+        None => { break; }
+        Some("--si") => {
+          si = true;
+        }
+        Some(other) => {
+          args.push_front(other.to_string());
+          break;
+        }
+      }
+    }
+
+    return Ok(BytesArgument {
+      common: common.build()?,
+      si: si,
+    });
+  }
+
+  #[allow(clippy::needless_return)]
+  fn parse_value(&self, settings: &Settings, name: &str, value: &str, exit_code: i32) -> Result<String, ArgError> {
+    let trimmed = value.trim();
+    let digits_end = trimmed.find(|c: char| !c.is_ascii_digit()).unwrap_or(trimmed.len());
+    let (digits, suffix) = trimmed.split_at(digits_end);
+
+    let count = digits
+        .parse::<u64>()
+        .ok_or_error(exit_code, settings.messages.get("invalid_bytes", &[("value", &value), ("name", &name)]))?;
+
+    let upper = suffix.to_uppercase();
+    let unit = upper.strip_suffix('B').unwrap_or(&upper);
+
+    // IEC suffixes such as `KiB` always mean powers of 1024, even with `--si`.
+    let binary = unit.len() == 2 && unit.ends_with('I');
+    let unit = if binary { &unit[..1] } else { unit };
+    let base: u64 = if self.si && !binary { 1000 } else { 1024 };
+
+    let exponent = if unit.is_empty() {
+      Some(0)
+    } else {
+      SUFFIXES.iter().position(|s| unit == s.to_string()).map(|position| position as u32 + 1)
+    };
+    let exponent = exponent
+        .ok_or_error(exit_code, settings.messages.get("unknown_byte_suffix", &[("suffix", &suffix), ("value", &value), ("name", &name)]))?;
+
+    let bytes = count
+        .checked_mul(base.pow(exponent))
+        .ok_or_error(exit_code, settings.messages.get("bytes_too_large", &[("value", &value), ("name", &name)]))?;
+    return Ok(bytes.to_string());
+  }
+}
+
+impl Argument for BytesArgument {
+  fn get_common(&self) -> &ArgumentCommon {
+    &self.common
+  }
+
+  fn get_common_mut(&mut self) -> &mut ArgumentCommon {
+    &mut self.common
+  }
+
+  #[allow(clippy::needless_return)]
+  fn get_debug_info(&self) -> String {
+    let mut description = format!("type: Bytes; {}", self.common.get_debug_info());
+    if self.si {
+      description.push_str("; SI units");
+    }
+    return description;
+  }
+
+  fn to_spec_json(&self) -> String {
+    self.common.to_spec_json("bytes", vec![("si", self.si.to_string())])
+  }
+
+  fn get_help_details(&self) -> Vec<HelpDetailSection> {
+    let multiplier = if self.si { "1000" } else { "1024" };
+
+    vec![
+        HelpDetailSection::Text(self.get_description().clone().unwrap_or(String::from("No details available."))),
+        HelpDetailSection::Text(format!(
+            "The value is a whole number of bytes, optionally followed by one of the suffixes K, M, G, T, \
+             or P. Each suffix multiplies the value by {multiplier} more than the one before it.")),
+    ]
+  }
+
+  #[allow(clippy::needless_return)]
+  fn check_default(&self, settings: &Settings) -> Result<(), ArgError> {
+    if self.get_default().is_some() {
+      self.parse_value(settings, &format!("{} default", self.get_name()), self.get_default().as_ref().unwrap(), DEFINITION_ERROR)?;
+    }
+    return Ok(());
+  }
+
+  fn default_output_value(&self, settings: &Settings, default: &str) -> String {
+    self.parse_value(settings, self.get_name(), default, DEFINITION_ERROR).unwrap()
+  }
+
+  fn consume(&self, settings: &Settings, arg: Option<String>, other_args: &mut VecDeque<String>) -> Result<Option<Vec<String>>, ArgError> {
+    self.consume_with_parser(
+      settings,
+      arg,
+      other_args,
+      &|name, value: &String| self.parse_value(settings, name, value, USER_ERROR))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::parse;
+  use super::super::test_support::strings;
+
+  #[allow(clippy::needless_return)]
+  fn parse_bytes(definition: &[&str], input: &[&str]) -> String {
+    let mut full_definition = vec!["--bytes", "size"];
+    full_definition.extend(definition);
+    return parse(&strings(&full_definition), &strings(input)).unwrap()["SIZE"][0].clone();
+  }
+
+  #[test]
+  fn default_is_expanded() {
+    assert_eq!(parse_bytes(&["--default", "4K"], &[]), "4096");
+    assert_eq!(parse_bytes(&["--default", "4K"], &["--size", "4K"]), "4096");
+    assert_eq!(parse_bytes(&["--si", "--default", "2M"], &[]), "2000000");
+  }
+
+  #[test]
+  fn iec_suffixes_are_binary_with_si() {
+    assert_eq!(parse_bytes(&["--si"], &["--size", "1KiB"]), "1024");
+    assert_eq!(parse_bytes(&["--si"], &["--size", "1K"]), "1000");
+    assert_eq!(parse_bytes(&["--si"], &["--size", "1KB"]), "1000");
+    assert_eq!(parse_bytes(&["--si"], &["--size", "2mib"]), "2097152");
+    assert_eq!(parse_bytes(&[], &["--size", "1KiB"]), "1024");
+  }
+}
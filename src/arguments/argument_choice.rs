@@ -5,14 +5,23 @@ use super::argument::HelpDetailSection;
 use super::argument_common::ArgumentCommon;
 use super::argument_common::ArgumentCommonBuilder;
 use super::argument_common::MatchResult;
+use super::errors::ArgError;
 use super::errors::error;
 use super::errors::OptionExt;
 use super::errors::DEFINITION_ERROR;
 use super::errors::USER_ERROR;
+use super::json::json_object;
+use super::json::json_optional_string;
+use super::json::json_string;
+use super::output_argument;
+use super::Settings;
 
 pub struct ChoiceArgument {
   common: ArgumentCommon,
   all_options: Vec<(String, OptionType)>,
+  allow_abbrev: bool,
+  emit_raw: bool,
+  resolved_default: Option<String>,
 }
 
 #[derive(Clone)]
@@ -22,25 +31,28 @@ enum OptionType {
 }
 
 impl ChoiceArgument {
-  pub fn new(args: &mut VecDeque<String>) -> Self {
+  #[allow(clippy::needless_return, clippy::redundant_field_names, clippy::unnecessary_unwrap)]
+  pub fn new(args: &mut VecDeque<String>) -> Result<Self, ArgError> {
     let mut common = ArgumentCommon::new_builder();
     let mut all_options = Vec::new();
+    let mut allow_abbrev = false;
+    let mut emit_raw = false;
 
     loop {
-      match common.parse_arguments(args).as_deref() {
+      match common.parse_arguments(args)?.as_deref() {
         None => { break; }
         Some("--map") => {
           let from = args.pop_front()
-              .unwrap_or_error(DEFINITION_ERROR, String::from("pair of values ({from} {to}) must be provided after --map"))
+              .ok_or_error(DEFINITION_ERROR, String::from("pair of values ({from} {to}) must be provided after --map"))?
               .to_string();
           let to = args.pop_front()
-              .unwrap_or_error(DEFINITION_ERROR, String::from("pair of values ({from} {to}) must be provided after --map"))
+              .ok_or_error(DEFINITION_ERROR, String::from("pair of values ({from} {to}) must be provided after --map"))?
               .to_string();
           all_options.push((from, OptionType::Mapping(to)));
         }
         Some("--option") => {
           let from = args.pop_front()
-              .unwrap_or_error(DEFINITION_ERROR, String::from("option must be provided after --option"))
+              .ok_or_error(DEFINITION_ERROR, String::from("option must be provided after --option"))?
               .to_string();
           let description = args.pop_front();
           if description.is_none() {
@@ -52,6 +64,17 @@ impl ChoiceArgument {
             all_options.push((from, OptionType::Actual(Some(description.unwrap()))));
           }
         }
+        Some("--options-file") => {
+          let path = args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("file path must be provided after --options-file"))?;
+          all_options.extend(read_options_file(&path)?);
+        }
+        Some("--allow-abbrev") => {
+          allow_abbrev = true;
+        }
+        Some("--emit-raw") => {
+          emit_raw = true;
+        }
         Some(other) => {
           args.push_front(other.to_string());
           break;
@@ -59,10 +82,69 @@ impl ChoiceArgument {
       }
     }
 
-    return ChoiceArgument {
-      common: common.build(),
+    let common = common.build()?;
+
+    // The default has to be one of the options, and a mapped default resolves to its target.
+    let resolved_default = common.get_default().as_ref().map(|default| {
+      let (_, info) = all_options.iter()
+          .find(|(option, _)| option == default)
+          .ok_or_error(DEFINITION_ERROR, format!("Default \"{default}\" for argument {} is not one of its options", common.get_name()))?;
+      match info {
+        OptionType::Actual(_) => Ok(default.clone()),
+        OptionType::Mapping(actual) => Ok(actual.clone()),
+      }
+    }).transpose()?;
+
+    return Ok(ChoiceArgument {
+      common: common,
       all_options: all_options,
-    };
+      allow_abbrev: allow_abbrev,
+      emit_raw: emit_raw,
+      resolved_default: resolved_default,
+    });
+  }
+
+  /// Finds the option a value refers to, either exactly or, if enabled, by a unique prefix.
+  #[allow(clippy::needless_return)]
+  fn find_option(&self, settings: &Settings, value: &str) -> Result<Option<&(String, OptionType)>, ArgError> {
+    let exact = self.all_options.iter().find(|(option, _)| option == value);
+    if exact.is_some() || !self.allow_abbrev || value.is_empty() {
+      return Ok(exact);
+    }
+
+    let matches = self.all_options.iter()
+        .filter(|(option, _)| option.starts_with(value))
+        .collect::<Vec<&(String, OptionType)>>();
+
+    if matches.len() > 1 {
+      return Err(error(USER_ERROR, settings.messages.get("ambiguous_choice", &[
+          ("value", &value),
+          ("name", self.get_name()),
+          ("options", &matches.iter().map(|(option, _)| option.as_str()).collect::<Vec<&str>>().join(", ")),
+      ])));
+    }
+
+    return Ok(matches.first().copied());
+  }
+
+  /// Resolves a value given by the user to the option (or mapped value) that is written out.
+  #[allow(clippy::needless_return)]
+  fn parse_value(&self, settings: &Settings, value: &str) -> Result<String, ArgError> {
+    if let Some((option, info)) = self.find_option(settings, value)? {
+      return match info {
+        OptionType::Actual(_) => Ok(option.clone()),
+        OptionType::Mapping(actual) => Ok(actual.clone()),
+      }
+    }
+
+    return Err(error(USER_ERROR, settings.messages.get("unknown_choice", &[("value", &value), ("name", self.get_name())])));
+  }
+
+  /// Gets the name of the variable that holds the values as the user typed them, when `--emit-raw`
+  /// is used. The suffix is added in the same name style as the name, so `GENDER` gets `GENDER_RAW`
+  /// and `outMode` gets `outModeRaw`.
+  fn raw_name(&self) -> String {
+    self.common.get_name_style().append_word(self.get_name(), "raw")
   }
 }
 
@@ -71,6 +153,11 @@ impl Argument for ChoiceArgument {
     &self.common
   }
 
+  fn get_common_mut(&mut self) -> &mut ArgumentCommon {
+    &mut self.common
+  }
+
+  #[allow(clippy::needless_return, clippy::unnecessary_unwrap)]
   fn get_debug_info(&self) -> String {
     let mut description = format!("type: Choice; {}", self.common.get_debug_info());
     let mut first = true;
@@ -84,53 +171,256 @@ impl Argument for ChoiceArgument {
 
       description.push_str(from);
 
-      match info {
-        OptionType::Mapping(to) => {
-          description.push_str(" -> ");
-          description.push_str(to);
-        },
-        _ => {}
+      if let OptionType::Mapping(to) = info {
+        description.push_str(" -> ");
+        description.push_str(to);
       }
     }
+
+    if self.allow_abbrev {
+      description.push_str("; abbreviations allowed");
+    }
+
+    if self.emit_raw {
+      description.push_str("; raw values written to: ");
+      description.push_str(&self.raw_name());
+    }
+
+    if self.resolved_default.is_some() && self.resolved_default != *self.get_default() {
+      description.push_str("; default resolves to: ");
+      description.push_str(self.resolved_default.as_ref().unwrap());
+    }
     return description;
   }
 
+  fn to_spec_json(&self) -> String {
+    let options = self.all_options.iter()
+        .map(|(option, info)| match info {
+          OptionType::Actual(description) => json_object(vec![
+              ("name", json_string(option)),
+              ("description", json_optional_string(description)),
+          ]),
+          OptionType::Mapping(actual) => json_object(vec![
+              ("name", json_string(option)),
+              ("maps_to", json_string(actual)),
+          ]),
+        })
+        .collect::<Vec<String>>();
+
+    self.common.to_spec_json("choice", vec![
+        ("options", format!("[{}]", options.join(", "))),
+        ("allow_abbrev", self.allow_abbrev.to_string()),
+        ("emit_raw", self.emit_raw.to_string()),
+    ])
+  }
+
   fn get_help_details(&self) -> Vec<HelpDetailSection> {
     let mut lines = vec![
         HelpDetailSection::Text(self.get_description().clone().unwrap_or(String::from("No details available."))),
         HelpDetailSection::Text(String::from("The possible options are:")),
     ];
 
+    // The default is always one of the options, either an actual one or the source of a mapping.
     for (option, info) in &self.all_options {
-      lines.push(HelpDetailSection::ListItem(format!("{} - {}", option,
+      lines.push(HelpDetailSection::ListItem(format!("{}{} - {}",
+          option,
+          if self.get_default().as_ref() == Some(option) { " (default)" } else { "" },
           match info {
             OptionType::Actual(description) => description.clone().unwrap_or(String::from("No details available.")),
             OptionType::Mapping(actual) => format!("Identical to '{actual}'"),
           })));
     }
 
+    if self.allow_abbrev {
+      lines.push(HelpDetailSection::Text(String::from(
+          "Any option can be shortened to a prefix, as long as the prefix matches only one option.")));
+    }
+
     lines
   }
 
-  fn consume(&self, arg: Option<String>, other_args: &mut VecDeque<String>) -> Option<String> {
-    let value = match self.common.check_flag_match(arg) {
-      MatchResult::NoMatch => return None,
-      MatchResult::MatchWithValue(_flag, value) => value,
-      MatchResult::MatchWithoutValue => other_args.pop_front()
-            .unwrap_or_error(USER_ERROR, format!("No value provided for argument {}", self.get_name()))
-    };
+  fn get_extra_names(&self) -> Vec<String> {
+    if self.emit_raw { vec![self.raw_name()] } else { Vec::new() }
+  }
 
-    for (option, info) in &self.all_options {
-      if option == &value {
-        return match info {
-          OptionType::Actual(_) => Some(value.clone()),
-          OptionType::Mapping(actual) => Some(actual.clone()),
-        }
+  /// With `--emit-raw` the collected values are what the user typed, and are only resolved to
+  /// options here. They were already checked when they were consumed.
+  #[allow(clippy::needless_return)]
+  fn output_variables(&self, settings: &Settings, values: &[String]) -> Vec<(String, Vec<String>)> {
+    if !self.emit_raw {
+      return vec![(self.get_name().clone(), values.to_vec())];
+    }
+
+    let resolved = values.iter().map(|value| self.parse_value(settings, value).unwrap()).collect::<Vec<String>>();
+    return vec![(self.get_name().clone(), resolved), (self.raw_name(), values.to_vec())];
+  }
+
+  #[allow(clippy::needless_return)]
+  fn default_output_value(&self, _settings: &Settings, default: &str) -> String {
+    if self.emit_raw {
+      return default.to_string();
+    } else {
+      return self.resolved_default.clone().unwrap();
+    }
+  }
+
+  fn output_missing(&self, settings: &Settings) {
+    if self.is_repeated() {
+      self.output_values(settings, &[]);
+    } else {
+      output_argument(settings, self.get_name(), "", self.is_secret());
+      if self.emit_raw {
+        output_argument(settings, &self.raw_name(), "", self.is_secret());
       }
     }
+  }
+
+  fn get_completion_values(&self) -> Vec<String> {
+    self.all_options.iter().map(|(option, _)| option.clone()).collect()
+  }
 
-    error(USER_ERROR, format!("Value \"{value}\" not recognized for argument {}", self.get_name()));
-    panic!("");
+  #[allow(clippy::needless_return)]
+  fn consume(&self, settings: &Settings, arg: Option<String>, other_args: &mut VecDeque<String>) -> Result<Option<Vec<String>>, ArgError> {
+    let value = match self.common.check_flag_match(arg) {
+      MatchResult::NoMatch => return Ok(None),
+      MatchResult::MatchWithValue(_flag, value) => self.common.resolve_stdin(settings, value)?,
+      MatchResult::MatchWithoutValue => self.common.resolve_stdin(settings, other_args.pop_front()
+            .ok_or_error(USER_ERROR, settings.messages.get("no_value", &[("name", self.get_name())]))?)?
+    };
+
+    // Values are always checked here, but with `--emit-raw` they are kept as typed until output.
+    let values = self.common.split_value(value).iter()
+        .map(|value| {
+          let resolved = self.parse_value(settings, value)?;
+          Ok(if self.emit_raw { value.clone() } else { resolved })
+        })
+        .collect::<Result<Vec<String>, ArgError>>()?;
+    return Ok(Some(values));
   }
 }
 
+/// Reads options from a file, one per line. A tab separates an option from its description. Blank
+/// lines are skipped.
+#[allow(clippy::needless_return)]
+fn read_options_file(path: &str) -> Result<Vec<(String, OptionType)>, ArgError> {
+  let contents = std::fs::read_to_string(path)
+      .ok_or_error(DEFINITION_ERROR, format!("Unable to read options file '{path}'"))?;
+
+  return Ok(contents
+      .lines()
+      .filter(|line| !line.trim().is_empty())
+      .map(|line| match line.split_once('\t') {
+        None => (line.trim().to_string(), OptionType::Actual(None)),
+        Some((option, description)) => (option.trim().to_string(), OptionType::Actual(Some(description.trim().to_string()))),
+      })
+      .collect());
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::test_support::eval_in;
+  use super::super::test_support::parse_values;
+  use super::super::test_support::run;
+  use super::super::ArgError;
+
+  const MODE: [&str; 9] = ["--choice", "mode", "--option", "production", "--map", "prod", "production", "--option", "dev"];
+
+  #[allow(clippy::needless_return)]
+  fn definition(extra: &[&'static str]) -> Vec<&'static str> {
+    let mut definition = MODE.to_vec();
+    definition.extend(extra);
+    return definition;
+  }
+
+  #[test]
+  fn repeated_choices_are_mapped_one_by_one() {
+    assert_eq!(
+        parse_values(&definition(&["--repeated"]), &["--mode", "prod", "--mode", "dev", "--mode", "production"]).unwrap()["MODE"],
+        ["production", "dev", "production"]);
+    assert_eq!(
+        parse_values(&definition(&["--repeated"]), &["--mode", "prod", "--mode", "test"]),
+        Err(ArgError::User(String::from("Value \"test\" not recognized for argument MODE"))));
+  }
+
+  #[test]
+  fn repeated_choices_are_written_as_lists() {
+    let mut args = definition(&["--repeated", "--"]);
+    args.extend(["--mode", "prod", "--mode", "dev"]);
+    assert_eq!(run(&args).0, "MODE='2'\nMODE_0='production'\nMODE_1='dev'\n");
+
+    let mut args = definition(&["--repeated", "--as-array", "--"]);
+    args.extend(["--mode", "dev", "--mode", "prod"]);
+    assert_eq!(eval_in("bash", &run(&args).0, "printf '<%s>' \"${MODE[@]}\""), "<dev><production>");
+  }
+
+  #[test]
+  fn parse_resolves_raw_choices_like_the_output() {
+    let values = parse_values(&definition(&["--emit-raw"]), &["--mode", "prod"]).unwrap();
+    assert_eq!(values["MODE"], ["production"]);
+    assert_eq!(values["MODE_RAW"], ["prod"]);
+    assert_eq!(run(&[&definition(&["--emit-raw", "--"])[..], &["--mode", "prod"]].concat()).0, "MODE='production'\nMODE_RAW='prod'\n");
+
+    let values = parse_values(&definition(&["--emit-raw", "--default", "prod"]), &[]).unwrap();
+    assert_eq!(values["MODE"], ["production"]);
+    assert_eq!(values["MODE_RAW"], ["prod"]);
+
+    let values = parse_values(&definition(&[]), &["--mode", "prod"]).unwrap();
+    assert_eq!(values["MODE"], ["production"]);
+    assert!(!values.contains_key("MODE_RAW"));
+  }
+
+  #[test]
+  fn option_descriptions_keep_paragraphs() {
+    let help = |format: &str| run(&[
+        "--help-format", format, "--columns", "60", "--program-name", "demo", "--auto-help",
+        "--choice", "mode",
+        "--option", "fast", "First paragraph about the fast mode that is long enough to wrap around.\n\nSecond paragraph.",
+        "--option", "slow", "Slow.",
+        "--", "--help"]).0;
+
+    assert!(help("text").contains(concat!(
+        "           •   fast - First paragraph about the fast mode\n",
+        "               that is long enough to wrap around.\n",
+        "\n",
+        "               Second paragraph.\n",
+        "\n",
+        "           •   slow - Slow.\n")));
+    assert!(help("markdown").contains(concat!(
+        "- fast - First paragraph about the fast mode that is long enough to wrap around.\n",
+        "\n",
+        "  Second paragraph.\n",
+        "- slow - Slow.")));
+    assert!(help("man").contains(concat!(
+        "\\(bu fast \\- First paragraph about the fast mode that is long enough to wrap around.\n",
+        ".IP\n",
+        "Second paragraph.\n",
+        ".IP\n",
+        "\\(bu slow \\- Slow.")));
+  }
+
+  #[test]
+  fn repeated_choices_keep_raw_values() {
+    let mut args = definition(&["--repeated", "--as-array", "--emit-raw", "--"]);
+    args.extend(["--mode", "prod", "--mode", "dev"]);
+    assert_eq!(
+        eval_in("bash", &run(&args).0, "printf '<%s>' \"${MODE[@]}\" \"${MODE_RAW[@]}\""),
+        "<production><dev><prod><dev>");
+  }
+
+  #[test]
+  fn raw_name_follows_the_name_style() {
+    let values = parse_values(&["--name-style", "camel", "--choice", "out-mode", "--option", "a", "--emit-raw"], &["--out-mode", "a"]).unwrap();
+    assert_eq!(values["outMode"], ["a"]);
+    assert_eq!(values["outModeRaw"], ["a"]);
+
+    let values = parse_values(&["--name-style", "snake_lower", "--choice", "out-mode", "--option", "a", "--emit-raw"], &["--out-mode", "a"]).unwrap();
+    assert_eq!(values["out_mode_raw"], ["a"]);
+  }
+
+  #[test]
+  fn raw_name_can_not_be_used_by_another_argument() {
+    assert_eq!(
+        parse_values(&[&definition(&["--emit-raw"])[..], &["--string", "x", "--name", "MODE_RAW"]].concat(), &[]),
+        Err(ArgError::Definition(String::from("Variable MODE_RAW written for MODE is already used by MODE_RAW"))));
+  }
+}
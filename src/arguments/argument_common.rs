@@ -2,8 +2,16 @@ use regex::Regex;
 use std::collections::VecDeque;
 
 use crate::arguments::errors::error;
+use crate::arguments::errors::ArgError;
 use crate::arguments::errors::OptionExt;
 use crate::arguments::errors::DEFINITION_ERROR;
+use crate::arguments::errors::USER_ERROR;
+use crate::arguments::json::json_object;
+use crate::arguments::json::json_optional;
+use crate::arguments::json::json_optional_string;
+use crate::arguments::json::json_string;
+use crate::arguments::json::json_string_array;
+use crate::arguments::Settings;
 
 struct ArgumentCommonBuilderData {
   name: Option<String>,
@@ -15,53 +23,117 @@ struct ArgumentCommonBuilderData {
   repeated: bool,
   ordinal: Option<u16>,
   catch_all: bool,
+  mutex_groups: Vec<String>,
+  one_of_groups: Vec<String>,
+  conflicts_with: Vec<String>,
+  as_array: bool,
+  stdin_dash: bool,
+  min_count: Option<usize>,
+  max_count: Option<usize>,
+  env: Option<String>,
+  split_on: Option<String>,
+  join: Option<String>,
 }
 
 pub trait ArgumentCommonBuilder {
-  fn parse_arguments(&mut self, args: &mut VecDeque<String>) -> Option<String>;
+  fn parse_arguments(&mut self, args: &mut VecDeque<String>) -> Result<Option<String>, ArgError>;
   fn add_flag(&mut self, flag: String);
-  fn build(self) -> ArgumentCommon;
+  fn get_all_flags(&self) -> &Vec<String>;
+  fn build(self) -> Result<ArgumentCommon, ArgError>;
 }
 
 impl ArgumentCommonBuilder for ArgumentCommonBuilderData { 
-  fn parse_arguments(&mut self, args: &mut VecDeque<String>) -> Option<String> {
+  fn parse_arguments(&mut self, args: &mut VecDeque<String>) -> Result<Option<String>, ArgError> {
     loop {
       match args.pop_front().as_deref() {
-        None => { return None; },
+        None => { return Ok(None); },
         Some("--required") => { self.required = true; },
         Some("--secret") => { self.secret = true; },
         Some("--repeated") | Some("--repeat") => { self.repeated = true; },
         Some("--catch-all") => { self.catch_all = true; },
+        Some("--as-array") => { self.as_array = true; },
+        Some("--stdin-dash") => { self.stdin_dash = true; },
         Some("--ordinal") | Some("--order") | Some("--ord") => {
             self.ordinal = Some(args.pop_front() 
-              .unwrap_or_error(DEFINITION_ERROR, String::from("ordinal position must be provided after --ordinal or --order or --ord"))
+              .ok_or_error(DEFINITION_ERROR, String::from("ordinal position must be provided after --ordinal or --order or --ord"))?
               .to_string()
               .parse::<u16>()
-              .unwrap_or_error(DEFINITION_ERROR, String::from("ordinal position must be an integer between 0 and 65,535")));
+              .ok_or_error(DEFINITION_ERROR, String::from("ordinal position must be an integer between 0 and 65,535"))?);
         }
         Some("--name") => {
-            self.name = Some(args.pop_front()
-              .unwrap_or_error(DEFINITION_ERROR, String::from("name must be provided after --name"))
-              .to_string());
+            let name = args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("name must be provided after --name"))?;
+            if !is_identifier(&name) {
+              return Err(error(DEFINITION_ERROR, format!(
+                  "Name '{name}' is not a valid variable name; it must start with a letter or underscore and contain only letters, digits, and underscores")));
+            }
+            self.name = Some(name);
           },
         Some("--default") => {
             self.default = Some(args.pop_front()
-              .unwrap_or_error(DEFINITION_ERROR, String::from("default value must be provided after --default"))
+              .ok_or_error(DEFINITION_ERROR, String::from("default value must be provided after --default"))?
               .to_string());
           },
         Some("--description") | Some("--desc") => {
             self.description = Some(args.pop_front()
-              .unwrap_or_error(DEFINITION_ERROR, String::from("description must be provided after --desc or --description"))
+              .ok_or_error(DEFINITION_ERROR, String::from("description must be provided after --desc or --description"))?
+              .to_string());
+          },
+        Some("--min-count") => {
+            self.min_count = Some(args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("count must be provided after --min-count"))?
+              .parse::<usize>()
+              .ok_or_error(DEFINITION_ERROR, String::from("--min-count must be a non-negative integer"))?);
+          },
+        Some("--max-count") => {
+            self.max_count = Some(args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("count must be provided after --max-count"))?
+              .parse::<usize>()
+              .ok_or_error(DEFINITION_ERROR, String::from("--max-count must be a non-negative integer"))?);
+          },
+        Some("--env") => {
+            let env = args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("variable name must be provided after --env"))?;
+            if !is_identifier(&env) {
+              return Err(error(DEFINITION_ERROR, format!("Environment variable name '{env}' is not a valid variable name")));
+            }
+            self.env = Some(env);
+          },
+        Some("--split-on") => {
+            let delimiter = args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("delimiter must be provided after --split-on"))?;
+            if delimiter.is_empty() {
+              return Err(error(DEFINITION_ERROR, String::from("delimiter provided after --split-on can not be empty")));
+            }
+            self.split_on = Some(delimiter);
+          },
+        Some("--join") => {
+            self.join = Some(args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("delimiter must be provided after --join"))?);
+          },
+        Some("--one-of") => {
+            self.one_of_groups.push(args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("group name must be provided after --one-of"))?
+              .to_string());
+          },
+        Some("--conflicts-with") => {
+            self.conflicts_with.push(args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("argument name must be provided after --conflicts-with"))?
+              .to_string());
+          },
+        Some("--mutex") => {
+            self.mutex_groups.push(args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("group name must be provided after --mutex"))?
               .to_string());
           },
         Some("--flag") => {
             self.all_flags.push(args.pop_front()
-              .unwrap_or_error(DEFINITION_ERROR, String::from("flag name must be provided after --flag"))
+              .ok_or_error(DEFINITION_ERROR, String::from("flag name must be provided after --flag"))?
               .to_string());
           },
         Some(other) => {
           if other.starts_with("-") {
-            return Some(other.to_string());
+            return Ok(Some(other.to_string()));
           } else {
             self.all_flags.push(format!("--{other}"));
           }
@@ -74,21 +146,51 @@ impl ArgumentCommonBuilder for ArgumentCommonBuilderData {
     self.all_flags.push(flag);
   }
 
-  fn build(self) -> ArgumentCommon {
+  fn get_all_flags(&self) -> &Vec<String> {
+    &self.all_flags
+  }
+
+  #[allow(clippy::redundant_field_names, clippy::unnecessary_unwrap)]
+  fn build(self) -> Result<ArgumentCommon, ArgError> {
+    let derived_name = self.name.is_none();
     let mut name = self.name;
     if name.is_none() {
-      name = Some(fix_name(self.all_flags.get(0)
-          .cloned()
-          .unwrap_or_error(DEFINITION_ERROR, String::from("no name or flags provided for argument"))));
+      name = Some(fix_name(self.all_flags.first()
+          .ok_or_error(DEFINITION_ERROR, String::from("no name or flags provided for argument"))?, NameStyle::SnakeUpper));
     }
     let name = name.unwrap();
 
     if self.all_flags.is_empty() && !self.catch_all && self.ordinal.is_none() {
-      error(DEFINITION_ERROR, format!("{name} argument can not be set - no flags, no ordinal, and not a catch-all argument"))
+      return Err(error(DEFINITION_ERROR, format!("{name} argument can not be set - no flags, no ordinal, and not a catch-all argument")))
+    }
+
+    if (self.min_count.is_some() || self.max_count.is_some()) && !self.repeated {
+      return Err(error(DEFINITION_ERROR, format!("{name} argument must be repeated to use --min-count or --max-count")));
+    }
+
+    if self.split_on.is_some() && !self.repeated {
+      return Err(error(DEFINITION_ERROR, format!("{name} argument must be repeated to use --split-on")));
     }
 
-    ArgumentCommon {
+    if self.join.is_some() && !self.repeated {
+      return Err(error(DEFINITION_ERROR, format!("{name} argument must be repeated to use --join")));
+    }
+
+    if self.join.is_some() && self.as_array {
+      return Err(error(DEFINITION_ERROR, format!("{name} argument can not use both --join and --as-array")));
+    }
+
+    if self.min_count.is_some() && self.max_count.is_some() && self.min_count.unwrap() > self.max_count.unwrap() {
+      return Err(error(DEFINITION_ERROR, format!(
+          "{name} argument has a minimum count of {} which is above its maximum count of {}",
+          self.min_count.unwrap(),
+          self.max_count.unwrap())));
+    }
+
+    Ok(ArgumentCommon {
       name: name,
+      derived_name: derived_name,
+      name_style: NameStyle::SnakeUpper,
       all_flags: self.all_flags,
       default: self.default,
       description: self.description,
@@ -97,12 +199,24 @@ impl ArgumentCommonBuilder for ArgumentCommonBuilderData {
       repeated: self.repeated,
       ordinal: self.ordinal,
       catch_all: self.catch_all,
-    }
+      mutex_groups: self.mutex_groups,
+      one_of_groups: self.one_of_groups,
+      conflicts_with: self.conflicts_with,
+      as_array: self.as_array,
+      stdin_dash: self.stdin_dash,
+      min_count: self.min_count,
+      max_count: self.max_count,
+      env: self.env,
+      split_on: self.split_on,
+      join: self.join,
+    })
   }
 }
 
 pub struct ArgumentCommon {
   name: String,
+  derived_name: bool,
+  name_style: NameStyle,
   all_flags: Vec<String>,
   default: Option<String>,
   description: Option<String>,
@@ -111,10 +225,21 @@ pub struct ArgumentCommon {
   repeated: bool,
   ordinal: Option<u16>,
   catch_all: bool,
+  mutex_groups: Vec<String>,
+  one_of_groups: Vec<String>,
+  conflicts_with: Vec<String>,
+  as_array: bool,
+  stdin_dash: bool,
+  min_count: Option<usize>,
+  max_count: Option<usize>,
+  env: Option<String>,
+  split_on: Option<String>,
+  join: Option<String>,
 }
 
 impl ArgumentCommon {
   pub fn get_name(&self) -> &String { &self.name }
+  pub fn get_name_style(&self) -> NameStyle { self.name_style }
   pub fn get_all_flags(&self) -> &Vec<String> { &self.all_flags }
   pub fn get_default(&self) -> &Option<String> { &self.default }
   pub fn get_description(&self) -> &Option<String> { &self.description }
@@ -123,6 +248,42 @@ impl ArgumentCommon {
   pub fn get_repeated(&self) -> bool { self.repeated }
   pub fn get_ordinal(&self) -> &Option<u16> { &self.ordinal }
   pub fn get_catch_all(&self) -> bool { self.catch_all }
+  pub fn get_mutex_groups(&self) -> &Vec<String> { &self.mutex_groups }
+  pub fn get_one_of_groups(&self) -> &Vec<String> { &self.one_of_groups }
+  pub fn get_conflicts_with(&self) -> &Vec<String> { &self.conflicts_with }
+  pub fn get_as_array(&self) -> bool { self.as_array }
+  pub fn get_min_count(&self) -> &Option<usize> { &self.min_count }
+  pub fn get_max_count(&self) -> &Option<usize> { &self.max_count }
+  pub fn get_env(&self) -> &Option<String> { &self.env }
+  pub fn get_split_on(&self) -> &Option<String> { &self.split_on }
+  pub fn get_join(&self) -> &Option<String> { &self.join }
+
+  pub fn add_flag(&mut self, flag: String) { self.all_flags.push(flag); }
+
+  /// Reads the value from the prefixed variable name when there is no `--env` of its own, used by
+  /// `--env-import`.
+  pub fn import_env(&mut self, prefix: &str) {
+    if self.env.is_none() {
+      self.env = Some(format!("{prefix}{}", self.name));
+    }
+  }
+
+  /// Rebuilds the variable name from the first flag using the given name style. Names set
+  /// explicitly with `--name` are left alone.
+  #[allow(clippy::needless_return)]
+  pub fn apply_name_style(&mut self, style: NameStyle) -> Result<(), ArgError> {
+    self.name_style = style;
+    if self.derived_name {
+      let flag = self.all_flags.first().unwrap();
+      self.name = fix_name(flag, style);
+      if style != NameStyle::Raw && !is_identifier(&self.name) {
+        return Err(error(DEFINITION_ERROR, format!(
+            "Name '{}' made from flag '{flag}' is not a valid variable name; use --name to choose one",
+            self.name)));
+      }
+    }
+    return Ok(());
+  }
 
   pub fn new_builder() -> impl ArgumentCommonBuilder {
     ArgumentCommonBuilderData {
@@ -135,9 +296,20 @@ impl ArgumentCommon {
       repeated: false,
       ordinal: None,
       catch_all: false,
+      mutex_groups: Vec::new(),
+      one_of_groups: Vec::new(),
+      conflicts_with: Vec::new(),
+      as_array: false,
+      stdin_dash: false,
+      min_count: None,
+      max_count: None,
+      env: None,
+      split_on: None,
+      join: None,
     }
   }
 
+  #[allow(clippy::needless_return, clippy::unnecessary_unwrap)]
   pub fn get_debug_info(&self) -> String {
     let mut description = format!("name: {}", self.name);
     description.push_str("; flags: ");
@@ -154,19 +326,112 @@ impl ArgumentCommon {
     if self.catch_all {
       description.push_str("; catch-all");
     }
+    if self.as_array {
+      description.push_str("; as array");
+    }
+    if self.stdin_dash {
+      description.push_str("; stdin dash");
+    }
+    if self.min_count.is_some() {
+      description.push_str(&format!("; min count: {}", self.min_count.unwrap()));
+    }
+    if self.max_count.is_some() {
+      description.push_str(&format!("; max count: {}", self.max_count.unwrap()));
+    }
+    if !self.mutex_groups.is_empty() {
+      description.push_str("; mutex: ");
+      description.push_str(&self.mutex_groups.join(", "));
+    }
+    if !self.one_of_groups.is_empty() {
+      description.push_str("; one of: ");
+      description.push_str(&self.one_of_groups.join(", "));
+    }
+    if !self.conflicts_with.is_empty() {
+      description.push_str("; conflicts with: ");
+      description.push_str(&self.conflicts_with.join(", "));
+    }
+    if self.split_on.is_some() {
+      description.push_str(&format!("; split on: '{}'", self.split_on.as_ref().unwrap()));
+    }
+    if self.join.is_some() {
+      description.push_str(&format!("; joined with: '{}'", self.join.as_ref().unwrap()));
+    }
+    if self.env.is_some() {
+      description.push_str("; env: ");
+      description.push_str(self.env.as_ref().unwrap());
+    }
     if self.default.is_some() {
       description.push_str("; default: ");
-      description.push_str(&self.default.as_ref().unwrap());
+      description.push_str(if self.secret { "****" } else { self.default.as_ref().unwrap() });
     }
 
     if self.description.is_some() {
       description.push_str("; description: ");
-      description.push_str(&self.description.as_ref().unwrap());
+      description.push_str(self.description.as_ref().unwrap());
     }
 
     return description;
   }
 
+  /// Describes the argument as a JSON object. `extra_fields` holds the type-specific fields, with
+  /// values that are already JSON encoded.
+  #[allow(clippy::needless_return)]
+  pub fn to_spec_json(&self, argument_type: &str, extra_fields: Vec<(&str, String)>) -> String {
+    let mut fields = vec![
+        ("name", json_string(&self.name)),
+        ("type", json_string(argument_type)),
+        ("flags", json_string_array(&self.all_flags)),
+        ("default", json_optional_string(&self.default)),
+        ("description", json_optional_string(&self.description)),
+        ("required", self.required.to_string()),
+        ("secret", self.secret.to_string()),
+        ("repeated", self.repeated.to_string()),
+        ("catch_all", self.catch_all.to_string()),
+        ("ordinal", json_optional(&self.ordinal)),
+        ("mutex_groups", json_string_array(&self.mutex_groups)),
+        ("one_of_groups", json_string_array(&self.one_of_groups)),
+        ("conflicts_with", json_string_array(&self.conflicts_with)),
+        ("as_array", self.as_array.to_string()),
+        ("stdin_dash", self.stdin_dash.to_string()),
+        ("min_count", json_optional(&self.min_count)),
+        ("max_count", json_optional(&self.max_count)),
+        ("env", json_optional_string(&self.env)),
+        ("split_on", json_optional_string(&self.split_on)),
+        ("join", json_optional_string(&self.join)),
+    ];
+    fields.extend(extra_fields);
+
+    return json_object(fields);
+  }
+
+  /// Splits a single value into several on the `--split-on` delimiter. Without one, the value is
+  /// returned on its own.
+  pub fn split_value(&self, value: String) -> Vec<String> {
+    match &self.split_on {
+      None => vec![value],
+      Some(delimiter) => value.split(delimiter.as_str()).map(String::from).collect(),
+    }
+  }
+
+  /// Replaces a value of `-` with the contents of standard input when `--stdin-dash` is set. A single
+  /// trailing newline is removed. Standard input can only be read once per invocation.
+  #[allow(clippy::needless_return)]
+  pub fn resolve_stdin(&self, settings: &Settings, value: String) -> Result<String, ArgError> {
+    if !self.stdin_dash || value != "-" {
+      return Ok(value);
+    }
+
+    if settings.stdin_read.replace(true) {
+      return Err(error(USER_ERROR, settings.messages.get("stdin_reused", &[("name", &self.name)])));
+    }
+
+    let contents = std::io::read_to_string(std::io::stdin())
+        .ok_or_error(USER_ERROR, settings.messages.get("stdin_unreadable", &[("name", &self.name)]))?;
+    let contents = contents.strip_suffix('\n').unwrap_or(&contents);
+    return Ok(contents.strip_suffix('\r').unwrap_or(contents).to_string());
+  }
+
+  #[allow(clippy::needless_return)]
   pub fn check_flag_match(&self, flag: Option<String>) -> MatchResult {
     match flag {
       None => { return MatchResult::MatchWithoutValue; },
@@ -195,13 +460,109 @@ pub enum MatchResult {
   NoMatch,
 }
 
-fn fix_name(name: String) -> String {
-  Regex::new(r"[a-zA-Z0-9]+")
+/// Checks whether the text can be used as (the start of) a shell variable name.
+pub fn is_identifier(text: &str) -> bool {
+  Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap().is_match(text)
+}
+
+/// How variable names are built from the runs of letters and digits in an argument's first flag.
+#[derive(Clone, Copy, PartialEq)]
+pub enum NameStyle {
+  SnakeUpper,
+  SnakeLower,
+  SnakePreserve,
+  Camel,
+  /// The flag itself without its leading dashes, for `--raw-names`. Only usable as an array key.
+  Raw,
+}
+
+impl NameStyle {
+  pub fn from_name(name: &str) -> Option<NameStyle> {
+    match name {
+      "snake_upper" => Some(NameStyle::SnakeUpper),
+      "snake_lower" => Some(NameStyle::SnakeLower),
+      "camel" => Some(NameStyle::Camel),
+      _ => None,
+    }
+  }
+
+  /// Adds a word to the end of a variable name, joined the way this style joins words. Snake case
+  /// follows the case of the name, so `GENDER` gets `GENDER_RAW` and `gender` gets `gender_raw`,
+  /// while camel case turns `outMode` into `outModeRaw`.
+  pub fn append_word(&self, name: &str, word: &str) -> String {
+    match self {
+      NameStyle::Camel => format!("{name}{}", capitalize(word)),
+      NameStyle::Raw => format!("{name}-{word}"),
+      _ if name.chars().any(|c| c.is_ascii_uppercase()) => format!("{name}_{}", word.to_uppercase()),
+      _ => format!("{name}_{}", word.to_lowercase()),
+    }
+  }
+}
+
+#[allow(clippy::needless_return)]
+fn fix_name(name: &str, style: NameStyle) -> String {
+  let words = Regex::new(r"[a-zA-Z0-9]+")
       .unwrap()
-      .find_iter(name.as_str())
+      .find_iter(name)
       .map(|m| m.as_str())
-      .collect::<Vec<&str>>()
-      .join("_")
-      .to_uppercase()
+      .collect::<Vec<&str>>();
+
+  match style {
+    NameStyle::SnakeUpper => words.join("_").to_uppercase(),
+    NameStyle::SnakeLower => words.join("_").to_lowercase(),
+    NameStyle::SnakePreserve => words.join("_"),
+    NameStyle::Raw => name.trim_start_matches('-').to_string(),
+    NameStyle::Camel => words
+        .iter()
+        .enumerate()
+        .map(|(i, word)| if i == 0 { word.to_lowercase() } else { capitalize(word) })
+        .collect(),
+  }
+}
+
+/// Lowercases a word apart from its first letter, which is uppercased, for camel case names.
+#[allow(clippy::needless_return)]
+fn capitalize(word: &str) -> String {
+  let word = word.to_lowercase();
+  let mut chars = word.chars();
+  return match chars.next() {
+    None => String::new(),
+    Some(first) => first.to_uppercase().chain(chars).collect(),
+  };
 }
 
+
+#[cfg(test)]
+mod tests {
+  use super::super::test_support::parse_values;
+  use super::super::ArgError;
+
+  #[test]
+  fn lower_name_case() {
+    let values = parse_values(
+        &["--name-case", "lower", "--string", "output-dir", "--string", "Log--LEVEL", "--string", "x", "--name", "KEEP"],
+        &["--output-dir", "/tmp", "--Log--LEVEL", "debug", "--x", "y"]).unwrap();
+    assert_eq!(values["output_dir"], ["/tmp"]);
+    assert_eq!(values["log_level"], ["debug"]);
+    assert_eq!(values["KEEP"], ["y"]);
+    assert_eq!(values.len(), 3);
+
+    assert_eq!(parse_values(&["--string", "output-dir"], &["--output-dir", "/tmp"]).unwrap()["OUTPUT_DIR"], ["/tmp"]);
+  }
+
+  #[test]
+  fn lower_names_must_be_identifiers() {
+    assert_eq!(
+        parse_values(&["--name-case", "lower", "--string", "2fast"], &[]),
+        Err(ArgError::Definition(String::from(
+            "Name '2fast' made from flag '--2fast' is not a valid variable name; use --name to choose one"))));
+    assert!(parse_values(&["--name-case", "lower", "--string", "2fast", "--name", "fast2"], &[]).is_ok());
+  }
+
+  #[test]
+  fn name_case_and_name_style_can_not_be_combined() {
+    assert_eq!(
+        parse_values(&["--name-case", "lower", "--name-style", "camel", "--string", "output-dir"], &[]),
+        Err(ArgError::Definition(String::from("--name-case and --name-style can not be used together"))));
+  }
+}
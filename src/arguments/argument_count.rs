@@ -0,0 +1,148 @@
+use std::collections::VecDeque;
+
+use super::argument::Argument;
+use super::argument::HelpDetailSection;
+use super::argument_common::ArgumentCommon;
+use super::argument_common::ArgumentCommonBuilder;
+use super::argument_common::MatchResult;
+use super::errors::ArgError;
+use super::errors::error;
+use super::errors::OptionExt;
+use super::errors::DEFINITION_ERROR;
+use super::errors::USER_ERROR;
+use super::output_argument;
+use super::Settings;
+
+pub struct CountArgument {
+  common: ArgumentCommon,
+}
+
+impl CountArgument {
+  #[allow(clippy::needless_return, clippy::redundant_field_names)]
+  pub fn new(args: &mut VecDeque<String>) -> Result<Self, ArgError> {
+    let mut common = ArgumentCommon::new_builder();
+    match common.parse_arguments(args)? {
+      None => { }
+      Some(other) => {
+        args.push_front(other);
+      }
+    }
+
+    let common = common.build()?;
+
+    if common.get_catch_all() {
+      return Err(error(DEFINITION_ERROR, format!("Count argument {} can not be catch-all", common.get_name())));
+
+    } else if common.get_ordinal().is_some() {
+      return Err(error(DEFINITION_ERROR, format!("Count argument {} can not be ordinal", common.get_name())));
+    }
+
+    return Ok(CountArgument {
+      common: common,
+    });
+  }
+}
+
+impl Argument for CountArgument {
+  fn get_common(&self) -> &ArgumentCommon {
+    &self.common
+  }
+
+  fn get_common_mut(&mut self) -> &mut ArgumentCommon {
+    &mut self.common
+  }
+
+  fn takes_value(&self) -> bool {
+    false
+  }
+
+  #[allow(clippy::needless_return)]
+  fn get_debug_info(&self) -> String {
+    return format!("type: Count; {}", self.common.get_debug_info());
+  }
+
+  fn to_spec_json(&self) -> String {
+    self.common.to_spec_json("count", Vec::new())
+  }
+
+  /// Count arguments always accept multiple occurrences; that is the whole point of them.
+  fn is_repeated(&self) -> bool {
+    true
+  }
+
+  fn get_help_flags(&self) -> Vec<String> {
+    self.common
+        .get_all_flags()
+        .iter()
+        .map(|flag| format!("{flag}[=<count>]"))
+        .collect()
+  }
+
+  fn get_help_details(&self) -> Vec<HelpDetailSection> {
+    vec![
+        HelpDetailSection::Text(self.get_description().clone().unwrap_or(String::from("No details available."))),
+        HelpDetailSection::Text(String::from(
+            "Each time this option is provided the count increases by one. A count can also be given \
+             directly with '=<count>'.")),
+    ]
+  }
+
+  fn writes_list(&self) -> bool {
+    false
+  }
+
+  /// Each occurrence adds to the count, so the variable holds their total.
+  fn output_variables(&self, _settings: &Settings, values: &[String]) -> Vec<(String, Vec<String>)> {
+    let total = values.iter()
+        .map(|value| value.parse::<u64>().unwrap())
+        .fold(0_u64, |total, count| total.saturating_add(count));
+
+    vec![(self.get_name().clone(), vec![total.to_string()])]
+  }
+
+  fn output_values(&self, settings: &Settings, values: &[String]) {
+    for (name, values) in self.output_variables(settings, values) {
+      output_argument(settings, &name, values.first().unwrap(), self.is_secret());
+    }
+  }
+
+  #[allow(clippy::needless_return)]
+  fn check_default(&self, _settings: &Settings) -> Result<(), ArgError> {
+    match self.get_default() {
+      None => {}
+      Some(default) => {
+        default
+            .parse::<u64>()
+            .ok_or_error(DEFINITION_ERROR, format!("Non-negative integer default required for argument {}, got '{default}'", self.get_name()))?;
+      }
+    }
+    return Ok(());
+  }
+
+  fn consume(&self, settings: &Settings, arg: Option<String>, _other_args: &mut VecDeque<String>) -> Result<Option<Vec<String>>, ArgError> {
+    match self.common.check_flag_match(arg) {
+      MatchResult::NoMatch => Ok(None),
+      MatchResult::MatchWithoutValue => Ok(Some(vec![String::from("1")])),
+      MatchResult::MatchWithValue(_flag, value) => Ok(Some(vec![value
+          .parse::<u64>()
+          .ok_or_error(USER_ERROR, settings.messages.get("invalid_unsigned", &[("name", self.get_name()), ("value", &value)]))?
+          .to_string()])),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::test_support::parse_values;
+  use super::super::test_support::strings;
+
+  #[test]
+  fn parse_returns_the_total_count() {
+    let definition = ["--count", "verbose", "--flag", "-v"];
+    assert_eq!(parse_values(&definition, &["-v", "-v", "-v"]).unwrap()["VERBOSE"], strings(&["3"]));
+    assert_eq!(parse_values(&definition, &["-v", "--verbose=4"]).unwrap()["VERBOSE"], strings(&["5"]));
+
+    let with_default = [&definition[..], &["--default", "2"]].concat();
+    assert_eq!(parse_values(&with_default, &[]).unwrap()["VERBOSE"], strings(&["2"]));
+  }
+}
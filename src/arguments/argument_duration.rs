@@ -0,0 +1,155 @@
+use std::collections::VecDeque;
+
+use super::argument::Argument;
+use super::argument::HelpDetailSection;
+use super::argument_common::ArgumentCommon;
+use super::argument_common::ArgumentCommonBuilder;
+use super::errors::ArgError;
+use super::errors::error;
+use super::errors::OptionExt;
+use super::errors::DEFINITION_ERROR;
+use super::errors::USER_ERROR;
+use super::Settings;
+
+pub struct DurationArgument {
+  common: ArgumentCommon,
+}
+
+impl DurationArgument {
+  #[allow(clippy::needless_return)]
+  pub fn new(args: &mut VecDeque<String>) -> Result<Self, ArgError> {
+    let mut common = ArgumentCommon::new_builder();
+    match common.parse_arguments(args)? {
+      None => { }
+      Some(other) => {
+        args.push_front(other);
+      }
+    }
+
+    return Ok(DurationArgument {
+      common: common.build()?,
+    });
+  }
+}
+
+impl Argument for DurationArgument {
+  fn get_common(&self) -> &ArgumentCommon {
+    &self.common
+  }
+
+  fn get_common_mut(&mut self) -> &mut ArgumentCommon {
+    &mut self.common
+  }
+
+  #[allow(clippy::needless_return)]
+  fn get_debug_info(&self) -> String {
+    return format!("type: Duration; {}", self.common.get_debug_info());
+  }
+
+  fn to_spec_json(&self) -> String {
+    self.common.to_spec_json("duration", Vec::new())
+  }
+
+  fn get_help_details(&self) -> Vec<HelpDetailSection> {
+    vec![
+        HelpDetailSection::Text(self.get_description().clone().unwrap_or(String::from("No details available."))),
+        HelpDetailSection::Text(String::from(
+            "The value is a duration made of one or more whole numbers, each followed by a unit: s \
+             (seconds), m (minutes), h (hours), or d (days). Units can be combined, as in '1h30m'. A \
+             value that is only a number, with no unit at all, is treated as seconds.")),
+    ]
+  }
+
+  #[allow(clippy::needless_return)]
+  fn check_default(&self, settings: &Settings) -> Result<(), ArgError> {
+    if self.get_default().is_some() {
+      parse_duration(settings, &format!("{} default", self.get_name()), self.get_default().as_ref().unwrap(), DEFINITION_ERROR)?;
+    }
+    return Ok(());
+  }
+
+  fn default_output_value(&self, settings: &Settings, default: &str) -> String {
+    parse_duration(settings, self.get_name(), default, DEFINITION_ERROR).unwrap()
+  }
+
+  fn consume(&self, settings: &Settings, arg: Option<String>, other_args: &mut VecDeque<String>) -> Result<Option<Vec<String>>, ArgError> {
+    self.consume_with_parser(
+      settings,
+      arg,
+      other_args,
+      &|name, value: &String| parse_duration(settings, name, value, USER_ERROR))
+  }
+}
+
+/// Converts a duration such as `90s`, `2h`, or `1h30m` to a total number of seconds, failing with the
+/// given code if it is invalid. A bare number is taken as seconds, but only when it is the whole
+/// value, so `1h30` is rejected rather than read as an hour and 30 seconds.
+#[allow(clippy::needless_return)]
+fn parse_duration(settings: &Settings, name: &str, value: &str, exit_code: i32) -> Result<String, ArgError> {
+  let mut total = 0_u64;
+  let mut digits = String::new();
+
+  if value.is_empty() {
+    return Err(error(exit_code, settings.messages.get("empty_duration", &[("name", &name)])));
+  }
+
+  for c in value.chars() {
+    if c.is_ascii_digit() {
+      digits.push(c);
+      continue;
+    }
+
+    let multiplier = match c {
+      's' => 1,
+      'm' => 60,
+      'h' => 60 * 60,
+      'd' => 24 * 60 * 60,
+      _ => {
+        return Err(error(exit_code, settings.messages.get("unknown_duration_unit", &[("unit", &c), ("value", &value), ("name", &name)])));
+      }
+    };
+
+    let count = digits
+        .parse::<u64>()
+        .ok_or_error(exit_code, settings.messages.get("invalid_duration", &[("value", &value), ("name", &name)]))?;
+    total = count
+        .checked_mul(multiplier)
+        .and_then(|seconds| total.checked_add(seconds))
+        .ok_or_error(exit_code, settings.messages.get("duration_too_large", &[("value", &value), ("name", &name)]))?;
+    digits.clear();
+  }
+
+  if !digits.is_empty() && digits.len() != value.len() {
+    return Err(error(exit_code, settings.messages.get("invalid_duration", &[("value", &value), ("name", &name)])));
+  }
+
+  if !digits.is_empty() {
+    total = digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|seconds| total.checked_add(seconds))
+        .ok_or_error(exit_code, settings.messages.get("duration_too_large", &[("value", &value), ("name", &name)]))?;
+  }
+
+  return Ok(total.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::test_support::parse_values;
+  use super::super::ArgError;
+
+  #[test]
+  fn default_is_written_in_seconds() {
+    assert_eq!(parse_values(&["--duration", "t", "--default", "2h"], &[]).unwrap()["T"], ["7200"]);
+    assert_eq!(parse_values(&["--duration", "t", "--default", "1h30m"], &[]).unwrap()["T"], ["5400"]);
+  }
+
+  #[test]
+  fn a_bare_number_must_be_the_whole_value() {
+    assert_eq!(parse_values(&["--duration", "t"], &["--t", "90"]).unwrap()["T"], ["90"]);
+    assert_eq!(
+        parse_values(&["--duration", "t"], &["--t", "1h30"]),
+        Err(ArgError::User(String::from("Invalid duration '1h30' provided for argument T"))));
+  }
+}
@@ -1,28 +1,151 @@
 use std::collections::VecDeque;
 
 use super::argument::Argument;
+use super::argument::HelpDetailSection;
 use super::argument_common::ArgumentCommon;
 use super::argument_common::ArgumentCommonBuilder;
+use super::errors::ArgError;
+use super::errors::error;
 use super::errors::OptionExt;
+use super::errors::DEFINITION_ERROR;
 use super::errors::USER_ERROR;
+use super::json::json_optional;
+use super::output_debug;
+use super::debug_value;
+use super::Settings;
 
 pub struct FloatArgument {
   common: ArgumentCommon,
+  min: Option<f64>,
+  max: Option<f64>,
+  clamp: bool,
+  allow_non_finite: bool,
+  decimal_comma: bool,
 }
 
 impl FloatArgument {
-  pub fn new(args: &mut VecDeque<String>) -> Self {
+  #[allow(clippy::needless_return, clippy::redundant_field_names, clippy::unnecessary_unwrap)]
+  pub fn new(args: &mut VecDeque<String>) -> Result<Self, ArgError> {
     let mut common = ArgumentCommon::new_builder();
-    match common.parse_arguments(args) {
-      None => { }
-      Some(other) => {
-        args.push_front(other);
+    let mut min = None;
+    let mut max = None;
+    let mut clamp = false;
+    let mut allow_non_finite = false;
+    let mut decimal_comma = false;
+
+    loop {
+      match common.parse_arguments(args)?.as_deref() {
+        None => { break; }
+        Some("--min") => {
+          let value = args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("minimum value must be provided after --min"))?;
+          let parsed = value
+              .parse::<f64>()
+              .ok_or_error(DEFINITION_ERROR, format!("Non-numeric value '{value}' provided for --min"))?;
+          // A NaN bound would never be crossed, and an infinite one is the same as no bound.
+          if !parsed.is_finite() {
+            return Err(error(DEFINITION_ERROR, format!("Value '{value}' provided for --min must be a finite number")));
+          }
+          min = Some(parsed);
+        }
+        Some("--max") => {
+          let value = args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("maximum value must be provided after --max"))?;
+          let parsed = value
+              .parse::<f64>()
+              .ok_or_error(DEFINITION_ERROR, format!("Non-numeric value '{value}' provided for --max"))?;
+          // A NaN bound would never be crossed, and an infinite one is the same as no bound.
+          if !parsed.is_finite() {
+            return Err(error(DEFINITION_ERROR, format!("Value '{value}' provided for --max must be a finite number")));
+          }
+          max = Some(parsed);
+        }
+        Some("--allow-non-finite") => {
+          allow_non_finite = true;
+        }
+        Some("--decimal-comma") => {
+          decimal_comma = true;
+        }
+        Some("--clamp") => {
+          clamp = true;
+        }
+        Some(other) => {
+          args.push_front(other.to_string());
+          break;
+        }
       }
     }
 
-    return FloatArgument {
-      common: common.build(),
+    let common = common.build()?;
+
+    if clamp && min.is_none() && max.is_none() {
+      return Err(error(DEFINITION_ERROR, format!("--clamp requires --min or --max for argument {}", common.get_name())));
+    }
+
+    if min.is_some() && max.is_some() && min.unwrap() > max.unwrap() {
+      return Err(error(DEFINITION_ERROR, format!(
+          "Float argument {} has a minimum of {} which is above its maximum of {}",
+          common.get_name(),
+          min.unwrap(),
+          max.unwrap())));
+    }
+
+    return Ok(FloatArgument {
+      common: common,
+      min: min,
+      max: max,
+      clamp: clamp,
+      allow_non_finite: allow_non_finite,
+      decimal_comma: decimal_comma,
+    });
+  }
+
+  /// Parses and checks a value. With `clamp`, values outside of the range are moved to the nearest
+  /// bound instead of failing.
+  #[allow(clippy::needless_return, clippy::unnecessary_unwrap)]
+  fn parse_value(&self, settings: &Settings, name: &str, value: &str, exit_code: i32, clamp: bool) -> Result<String, ArgError> {
+    // With `--decimal-comma` a single comma is the decimal point. A value with both a comma and a dot
+    // could be using either as a thousands separator, so it is rejected.
+    let normalized = if self.decimal_comma && !value.contains('.') && value.matches(',').count() <= 1 {
+      value.replace(',', ".")
+    } else {
+      value.to_string()
     };
+
+    let mut parsed = normalized
+        .parse::<f64>()
+        .ok_or_error(exit_code, settings.messages.get("invalid_number", &[("value", &value), ("name", &name)]))?;
+
+    if !parsed.is_finite() && !self.allow_non_finite {
+      return Err(error(exit_code, settings.messages.get("not_finite", &[("value", &value), ("name", &name)])));
+    }
+
+    // NaN fails every comparison, so it would slip past the range checks below.
+    if parsed.is_nan() && (self.min.is_some() || self.max.is_some()) {
+      return Err(error(exit_code, settings.messages.get("not_finite", &[("value", &value), ("name", &name)])));
+    }
+
+    if self.min.is_some() && parsed < self.min.unwrap() && clamp {
+      output_debug(settings, format!(
+          "Clamped value {} for {name} to the minimum of {}",
+          debug_value(self.is_secret(), parsed),
+          debug_value(self.is_secret(), self.min.unwrap())));
+      parsed = self.min.unwrap();
+    } else if self.min.is_some() && parsed < self.min.unwrap() {
+      return Err(error(exit_code, settings.messages.get("below_minimum", &[("value", &parsed), ("name", &name), ("min", &self.min.unwrap())])));
+    }
+
+    if self.max.is_some() && parsed > self.max.unwrap() && clamp {
+      output_debug(settings, format!(
+          "Clamped value {} for {name} to the maximum of {}",
+          debug_value(self.is_secret(), parsed),
+          debug_value(self.is_secret(), self.max.unwrap())));
+      parsed = self.max.unwrap();
+    } else if self.max.is_some() && parsed > self.max.unwrap() {
+      return Err(error(exit_code, settings.messages.get("above_maximum", &[("value", &parsed), ("name", &name), ("max", &self.max.unwrap())])));
+    }
+
+    return Ok(parsed.to_string());
   }
 }
 
@@ -31,18 +154,160 @@ impl Argument for FloatArgument {
     &self.common
   }
 
+  fn get_common_mut(&mut self) -> &mut ArgumentCommon {
+    &mut self.common
+  }
+
+  #[allow(clippy::needless_return, clippy::unnecessary_unwrap)]
   fn get_debug_info(&self) -> String {
-    return format!("type: Float; {}", self.common.get_debug_info());
+    let mut description = format!("type: Float; {}", self.common.get_debug_info());
+    if self.min.is_some() {
+      description.push_str(&format!("; min: {}", self.min.unwrap()));
+    }
+    if self.max.is_some() {
+      description.push_str(&format!("; max: {}", self.max.unwrap()));
+    }
+    if self.allow_non_finite {
+      description.push_str("; non-finite values allowed");
+    }
+    if self.decimal_comma {
+      description.push_str("; decimal comma");
+    }
+    if self.clamp {
+      description.push_str("; clamped to range");
+    }
+    return description;
+  }
+
+  fn to_spec_json(&self) -> String {
+    self.common.to_spec_json("float", vec![
+        ("min", json_optional(&self.min)),
+        ("max", json_optional(&self.max)),
+        ("allow_non_finite", self.allow_non_finite.to_string()),
+        ("decimal_comma", self.decimal_comma.to_string()),
+        ("clamp", self.clamp.to_string()),
+    ])
+  }
+
+  fn get_help_details(&self) -> Vec<HelpDetailSection> {
+    let mut lines = vec![
+        HelpDetailSection::Text(self.get_description().clone().unwrap_or(String::from("No details available."))),
+    ];
+
+    match (self.min, self.max) {
+      (Some(min), Some(max)) => {
+        lines.push(HelpDetailSection::Text(format!("The value must be between {min} and {max}, inclusive.")));
+      }
+      (Some(min), None) => {
+        lines.push(HelpDetailSection::Text(format!("The value must be at least {min}.")));
+      }
+      (None, Some(max)) => {
+        lines.push(HelpDetailSection::Text(format!("The value must be at most {max}.")));
+      }
+      (None, None) => {}
+    }
+
+    if self.clamp {
+      lines.push(HelpDetailSection::Text(String::from("Values outside of the range are changed to the nearest bound.")));
+    }
+
+    if self.decimal_comma {
+      lines.push(HelpDetailSection::Text(String::from("A comma can be used as the decimal point, as in '3,14'.")));
+    }
+
+    if self.allow_non_finite {
+      lines.push(HelpDetailSection::Text(String::from("NaN and infinite values are also accepted.")));
+    }
+
+    lines
+  }
+
+  #[allow(clippy::needless_return)]
+  fn check_default(&self, settings: &Settings) -> Result<(), ArgError> {
+    if self.get_default().is_some() {
+      self.parse_value(settings, &format!("{} default", self.get_name()), self.get_default().as_ref().unwrap(), DEFINITION_ERROR, false)?;
+    }
+    return Ok(());
+  }
+
+  fn default_output_value(&self, settings: &Settings, default: &str) -> String {
+    self.parse_value(settings, self.get_name(), default, DEFINITION_ERROR, false).unwrap()
   }
 
-  fn consume(&self, arg: Option<String>, other_args: &mut VecDeque<String>) -> Option<String> {
+  fn consume(&self, settings: &Settings, arg: Option<String>, other_args: &mut VecDeque<String>) -> Result<Option<Vec<String>>, ArgError> {
     self.consume_with_parser(
+      settings,
       arg,
       other_args,
-      |name, value: &String| value
-          .parse::<f64>()
-          .unwrap_or_error(USER_ERROR, format!("Non-numeric value '{value}' provided for argument {name}"))
-          .to_string())
+      &|name, value: &String| self.parse_value(settings, name, value, USER_ERROR, self.clamp))
   }
 }
 
+#[cfg(test)]
+mod tests {
+  use super::super::parse;
+  use super::super::test_support::parse_values;
+  use super::super::test_support::strings;
+  use super::super::ArgError;
+
+  #[allow(clippy::needless_return)]
+  fn parse_float(definition: &[&str], input: &[&str]) -> Result<String, ArgError> {
+    let mut full_definition = vec!["--float", "ratio"];
+    full_definition.extend(definition);
+    return parse(&strings(&full_definition), &strings(input)).map(|values| values["RATIO"][0].clone());
+  }
+
+  #[test]
+  fn bounds_must_be_finite() {
+    for bound in ["nan", "NaN", "inf", "-inf", "infinity"] {
+      assert_eq!(
+          parse_float(&["--min", bound], &[]),
+          Err(ArgError::Definition(format!("Value '{bound}' provided for --min must be a finite number"))));
+      assert_eq!(
+          parse_float(&["--max", bound], &[]),
+          Err(ArgError::Definition(format!("Value '{bound}' provided for --max must be a finite number"))));
+    }
+  }
+
+  #[test]
+  fn default_is_written_like_a_value() {
+    assert_eq!(parse_values(&["--float", "f", "--decimal-comma", "--default", "1,5"], &[]).unwrap()["F"], ["1.5"]);
+    assert_eq!(parse_values(&["--float", "f", "--default", "2.50"], &[]).unwrap()["F"], ["2.5"]);
+  }
+
+  #[test]
+  fn non_finite_values() {
+    let not_finite = |value: &str| Err(ArgError::User(format!("Value '{value}' provided for argument RATIO must be a finite number")));
+
+    assert_eq!(parse_float(&[], &["--ratio", "2.5"]), Ok(String::from("2.5")));
+    assert_eq!(parse_float(&[], &["--ratio", "-1e-3"]), Ok(String::from("-0.001")));
+    for value in ["nan", "NaN", "inf", "-inf", "infinity"] {
+      assert_eq!(parse_float(&[], &["--ratio", value]), not_finite(value));
+    }
+
+    let allowed = ["--allow-non-finite"];
+    assert_eq!(parse_float(&allowed, &["--ratio", "2.5"]), Ok(String::from("2.5")));
+    assert_eq!(parse_float(&allowed, &["--ratio", "nan"]), Ok(String::from("NaN")));
+    assert_eq!(parse_float(&allowed, &["--ratio", "inf"]), Ok(String::from("inf")));
+    assert_eq!(parse_float(&allowed, &["--ratio", "-inf"]), Ok(String::from("-inf")));
+
+    let bounded = ["--allow-non-finite", "--min", "0", "--max", "1"];
+    assert_eq!(parse_float(&bounded, &["--ratio", "nan"]), not_finite("nan"));
+    assert_eq!(parse_float(&["--allow-non-finite", "--max", "1", "--clamp"], &["--ratio", "nan"]), not_finite("nan"));
+    assert_eq!(
+        parse_float(&bounded, &["--ratio", "inf"]),
+        Err(ArgError::User(String::from("Value 'inf' for RATIO is above the maximum of 1"))));
+    assert_eq!(
+        parse_float(&bounded, &["--ratio", "-inf"]),
+        Err(ArgError::User(String::from("Value '-inf' for RATIO is below the minimum of 0"))));
+    assert_eq!(parse_float(&["--allow-non-finite", "--min", "0", "--clamp"], &["--ratio", "-inf"]), Ok(String::from("0")));
+  }
+
+  #[test]
+  fn finite_bounds_are_checked() {
+    assert_eq!(parse_float(&["--min", "0", "--max", "1"], &["--ratio", "0.5"]), Ok(String::from("0.5")));
+    assert_eq!(
+        parse_float(&["--min", "0", "--max", "1"], &["--ratio", "1.5"]),
+        Err(ArgError::User(String::from("Value '1.5' for RATIO is above the maximum of 1"))));
+  }
+}
@@ -1,28 +1,141 @@
 use std::collections::VecDeque;
 
 use super::argument::Argument;
+use super::argument::HelpDetailSection;
 use super::argument_common::ArgumentCommon;
 use super::argument_common::ArgumentCommonBuilder;
+use super::errors::ArgError;
+use super::errors::error;
 use super::errors::OptionExt;
+use super::errors::DEFINITION_ERROR;
 use super::errors::USER_ERROR;
+use super::json::json_optional;
+use super::output_debug;
+use super::debug_value;
+use super::Settings;
 
 pub struct IntegerArgument {
   common: ArgumentCommon,
+  min: Option<i64>,
+  max: Option<i64>,
+  clamp: bool,
+  multiple_of: Option<i64>,
+  allow_thousands: bool,
 }
 
 impl IntegerArgument {
-  pub fn new(args: &mut VecDeque<String>) -> Self {
+  #[allow(clippy::needless_return, clippy::redundant_field_names, clippy::unnecessary_unwrap)]
+  pub fn new(args: &mut VecDeque<String>) -> Result<Self, ArgError> {
     let mut common = ArgumentCommon::new_builder();
-    match common.parse_arguments(args) {
-      None => { }
-      Some(other) => {
-        args.push_front(other);
+    let mut min = None;
+    let mut max = None;
+    let mut clamp = false;
+    let mut multiple_of = None;
+    let mut allow_thousands = false;
+
+    loop {
+      match common.parse_arguments(args)?.as_deref() {
+        None => { break; }
+        Some("--min") => {
+          let value = args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("minimum value must be provided after --min"))?;
+          min = Some(parse_integer(&value, false)
+              .ok_or_error(DEFINITION_ERROR, format!("Non-integer value '{value}' provided for --min"))?);
+        }
+        Some("--max") => {
+          let value = args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("maximum value must be provided after --max"))?;
+          max = Some(parse_integer(&value, false)
+              .ok_or_error(DEFINITION_ERROR, format!("Non-integer value '{value}' provided for --max"))?);
+        }
+        Some("--multiple-of") => {
+          let value = args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("step must be provided after --multiple-of"))?;
+          multiple_of = Some(parse_integer(&value, false)
+              .ok_or_error(DEFINITION_ERROR, format!("Non-integer value '{value}' provided for --multiple-of"))?);
+          if multiple_of.unwrap() <= 0 {
+            return Err(error(DEFINITION_ERROR, format!("Value '{value}' provided for --multiple-of must be greater than zero")));
+          }
+        }
+        Some("--allow-thousands") => {
+          allow_thousands = true;
+        }
+        Some("--clamp") => {
+          clamp = true;
+        }
+        Some(other) => {
+          args.push_front(other.to_string());
+          break;
+        }
       }
     }
 
-    return IntegerArgument {
-      common: common.build(),
-    };
+    let common = common.build()?;
+
+    if clamp && min.is_none() && max.is_none() {
+      return Err(error(DEFINITION_ERROR, format!("--clamp requires --min or --max for argument {}", common.get_name())));
+    }
+
+    if min.is_some() && max.is_some() && min.unwrap() > max.unwrap() {
+      return Err(error(DEFINITION_ERROR, format!(
+          "Integer argument {} has a minimum of {} which is above its maximum of {}",
+          common.get_name(),
+          min.unwrap(),
+          max.unwrap())));
+    }
+
+    // A clamped value is moved to a bound, so each bound has to pass --multiple-of as well.
+    let bad_bound = [min, max].into_iter().flatten().find(|bound| multiple_of.is_some_and(|step| bound % step != 0));
+    if clamp && bad_bound.is_some() {
+      return Err(error(DEFINITION_ERROR, format!(
+          "Integer argument {} uses --clamp, so its bound of {} must be a multiple of {}",
+          common.get_name(),
+          bad_bound.unwrap(),
+          multiple_of.unwrap())));
+    }
+
+    return Ok(IntegerArgument {
+      common: common,
+      min: min,
+      max: max,
+      clamp: clamp,
+      multiple_of: multiple_of,
+      allow_thousands: allow_thousands,
+    });
+  }
+
+  /// Parses and checks a value. With `clamp`, values outside of the range are moved to the nearest
+  /// bound instead of failing.
+  #[allow(clippy::needless_return, clippy::unnecessary_unwrap)]
+  fn parse_value(&self, settings: &Settings, name: &str, value: &str, exit_code: i32, clamp: bool) -> Result<String, ArgError> {
+    let mut parsed = parse_integer(value, self.allow_thousands)
+        .ok_or_error(exit_code, settings.messages.get("invalid_integer", &[("value", &value), ("name", &name)]))?;
+
+    if self.min.is_some() && parsed < self.min.unwrap() && clamp {
+      output_debug(settings, format!(
+          "Clamped value {} for {name} to the minimum of {}",
+          debug_value(self.is_secret(), parsed),
+          debug_value(self.is_secret(), self.min.unwrap())));
+      parsed = self.min.unwrap();
+    } else if self.min.is_some() && parsed < self.min.unwrap() {
+      return Err(error(exit_code, settings.messages.get("below_minimum", &[("value", &parsed), ("name", &name), ("min", &self.min.unwrap())])));
+    }
+
+    if self.max.is_some() && parsed > self.max.unwrap() && clamp {
+      output_debug(settings, format!(
+          "Clamped value {} for {name} to the maximum of {}",
+          debug_value(self.is_secret(), parsed),
+          debug_value(self.is_secret(), self.max.unwrap())));
+      parsed = self.max.unwrap();
+    } else if self.max.is_some() && parsed > self.max.unwrap() {
+      return Err(error(exit_code, settings.messages.get("above_maximum", &[("value", &parsed), ("name", &name), ("max", &self.max.unwrap())])));
+    }
+
+    if self.multiple_of.is_some() && parsed % self.multiple_of.unwrap() != 0 {
+      return Err(error(exit_code, settings.messages.get("not_multiple", &[("value", &parsed), ("name", &name), ("step", &self.multiple_of.unwrap())])));
+    }
+
+    return Ok(parsed.to_string());
   }
 }
 
@@ -31,18 +144,195 @@ impl Argument for IntegerArgument {
     &self.common
   }
 
+  fn get_common_mut(&mut self) -> &mut ArgumentCommon {
+    &mut self.common
+  }
+
+  #[allow(clippy::needless_return, clippy::unnecessary_unwrap)]
   fn get_debug_info(&self) -> String {
-    return format!("type: Integer; {}", self.common.get_debug_info());
+    let mut description = format!("type: Integer; {}", self.common.get_debug_info());
+    if self.min.is_some() {
+      description.push_str(&format!("; min: {}", self.min.unwrap()));
+    }
+    if self.max.is_some() {
+      description.push_str(&format!("; max: {}", self.max.unwrap()));
+    }
+    if self.multiple_of.is_some() {
+      description.push_str(&format!("; multiple of: {}", self.multiple_of.unwrap()));
+    }
+    if self.allow_thousands {
+      description.push_str("; thousands separators allowed");
+    }
+    if self.clamp {
+      description.push_str("; clamped to range");
+    }
+    return description;
+  }
+
+  fn to_spec_json(&self) -> String {
+    self.common.to_spec_json("integer", vec![
+        ("min", json_optional(&self.min)),
+        ("max", json_optional(&self.max)),
+        ("multiple_of", json_optional(&self.multiple_of)),
+        ("allow_thousands", self.allow_thousands.to_string()),
+        ("clamp", self.clamp.to_string()),
+    ])
   }
 
-  fn consume(&self, arg: Option<String>, other_args: &mut VecDeque<String>) -> Option<String> {
+  #[allow(clippy::unnecessary_unwrap)]
+  fn get_help_details(&self) -> Vec<HelpDetailSection> {
+    let mut lines = vec![
+        HelpDetailSection::Text(self.get_description().clone().unwrap_or(String::from("No details available."))),
+    ];
+
+    match (self.min, self.max) {
+      (Some(min), Some(max)) => {
+        lines.push(HelpDetailSection::Text(format!("The value must be between {min} and {max}, inclusive.")));
+      }
+      (Some(min), None) => {
+        lines.push(HelpDetailSection::Text(format!("The value must be at least {min}.")));
+      }
+      (None, Some(max)) => {
+        lines.push(HelpDetailSection::Text(format!("The value must be at most {max}.")));
+      }
+      (None, None) => {}
+    }
+
+    if self.clamp {
+      lines.push(HelpDetailSection::Text(String::from("Values outside of the range are changed to the nearest bound.")));
+    }
+
+    if self.multiple_of.is_some() {
+      lines.push(HelpDetailSection::Text(format!("The value must be a multiple of {}.", self.multiple_of.unwrap())));
+    }
+
+    lines.push(HelpDetailSection::Text(String::from(
+        "Hexadecimal (0x), octal (0o), and binary (0b) values are also accepted. Digits can be grouped \
+         with underscores, as in 1_000_000.")));
+
+    if self.allow_thousands {
+      lines.push(HelpDetailSection::Text(String::from("Commas can separate thousands, as in 1,000,000.")));
+    }
+
+    lines
+  }
+
+  #[allow(clippy::needless_return)]
+  fn check_default(&self, settings: &Settings) -> Result<(), ArgError> {
+    if self.get_default().is_some() {
+      self.parse_value(settings, &format!("{} default", self.get_name()), self.get_default().as_ref().unwrap(), DEFINITION_ERROR, false)?;
+    }
+    return Ok(());
+  }
+
+  fn default_output_value(&self, settings: &Settings, default: &str) -> String {
+    self.parse_value(settings, self.get_name(), default, DEFINITION_ERROR, false).unwrap()
+  }
+
+  fn consume(&self, settings: &Settings, arg: Option<String>, other_args: &mut VecDeque<String>) -> Result<Option<Vec<String>>, ArgError> {
     self.consume_with_parser(
+      settings,
       arg,
       other_args,
-      |name, value: &String| value
-          .parse::<i64>()
-          .unwrap_or_error(USER_ERROR, format!("Non-integer value '{value}' provided for argument {name}"))
-          .to_string())
+      &|name, value: &String| self.parse_value(settings, name, value, USER_ERROR, self.clamp))
   }
 }
 
+/// Parses a decimal integer, or a hexadecimal, octal, or binary integer prefixed with `0x`, `0o`, or
+/// `0b`. A leading sign is allowed before the prefix (e.g. `-0x1F`). Digits can be grouped with
+/// underscores, and decimal integers can use commas as thousands separators if `allow_thousands` is
+/// set.
+#[allow(clippy::needless_return)]
+fn parse_integer(value: &str, allow_thousands: bool) -> Option<i64> {
+  let (sign, unsigned) = match value.strip_prefix('-') {
+    Some(rest) => ("-", rest),
+    None => ("", value.strip_prefix('+').unwrap_or(value)),
+  };
+
+  let (radix, digits) = match unsigned.get(0..2).map(|prefix| prefix.to_lowercase()).as_deref() {
+    Some("0x") => (16, &unsigned[2..]),
+    Some("0o") => (8, &unsigned[2..]),
+    Some("0b") => (2, &unsigned[2..]),
+    _ => (10, unsigned),
+  };
+
+  if digits.starts_with('-') || digits.starts_with('+') {
+    return None;
+  }
+
+  let digits = if radix == 10 && allow_thousands { strip_thousands(digits)? } else { digits.to_string() };
+  let digits = strip_underscores(&digits)?;
+
+  return i64::from_str_radix(&format!("{sign}{digits}"), radix).ok();
+}
+
+/// Removes underscores that group digits. Underscores must sit between two digits, so `1_000` is
+/// accepted but `_1`, `1_` and `1__0` are not.
+#[allow(clippy::needless_return)]
+fn strip_underscores(digits: &str) -> Option<String> {
+  if digits.split('_').any(|group| group.is_empty()) {
+    return None;
+  }
+  return Some(digits.replace('_', ""));
+}
+
+/// Removes commas that separate thousands. The first group must have one to three digits and every
+/// other group exactly three, so `1,000` is accepted but `1,00`, `,100` and `1000,000` are not.
+/// Commas and underscores can't be mixed.
+#[allow(clippy::needless_return)]
+fn strip_thousands(digits: &str) -> Option<String> {
+  if !digits.contains(',') {
+    return Some(digits.to_string());
+  }
+
+  let groups = digits.split(',').collect::<Vec<&str>>();
+  if digits.contains('_')
+      || groups[0].is_empty()
+      || groups[0].len() > 3
+      || groups[1..].iter().any(|group| group.len() != 3) {
+    return None;
+  }
+  return Some(groups.concat());
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::ArgError;
+  use super::super::test_support::parse_values;
+  use super::super::test_support::run;
+
+  #[test]
+  fn default_is_written_like_a_value() {
+    assert_eq!(parse_values(&["--integer", "n", "--default", "0x10"], &[]).unwrap()["N"], ["16"]);
+    assert_eq!(parse_values(&["--integer", "n", "--default", "0x10"], &["--n", "0x10"]).unwrap()["N"], ["16"]);
+  }
+
+  #[test]
+  fn bounds_use_the_integer_syntax() {
+    let definition = ["--integer", "n", "--min", "-0x10", "--max", "1_000", "--multiple-of", "0b100"];
+    assert_eq!(parse_values(&definition, &["--n", "-16"]).unwrap()["N"], ["-16"]);
+    assert_eq!(parse_values(&definition, &["--n", "1000"]).unwrap()["N"], ["1000"]);
+    assert!(parse_values(&definition, &["--n", "1004"]).is_err());
+    assert!(parse_values(&definition, &["--n", "-20"]).is_err());
+    assert!(parse_values(&definition, &["--n", "6"]).is_err());
+  }
+
+  #[test]
+  fn clamp_notes_mask_secrets() {
+    let (output, _) = run(&["--debug", "--integer", "pin", "--max", "4321", "--clamp", "--", "--pin", "98765"]);
+    assert!(output.contains("Clamped value 98765 for PIN to the maximum of 4321"));
+
+    let (output, _) = run(&["--debug", "--integer", "pin", "--max", "4321", "--clamp", "--secret", "--", "--pin", "98765"]);
+    assert!(output.contains("Clamped value **** for PIN to the maximum of ****"));
+    assert_eq!(output.matches("98765").count(), 0);
+    assert!(output.contains("\nPIN='4321'\n"));
+  }
+
+  #[test]
+  fn clamped_bounds_must_be_multiples() {
+    assert_eq!(parse_values(&["--integer", "n", "--max", "10", "--clamp", "--multiple-of", "4"], &[]), Err(ArgError::Definition(String::from(
+        "Integer argument N uses --clamp, so its bound of 10 must be a multiple of 4"))));
+    assert_eq!(parse_values(&["--integer", "n", "--max", "12", "--clamp", "--multiple-of", "4"], &["--n", "50"]).unwrap()["N"], ["12"]);
+    assert_eq!(parse_values(&["--integer", "n", "--max", "10", "--multiple-of", "4"], &["--n", "8"]).unwrap()["N"], ["8"]);
+  }
+}
@@ -0,0 +1,146 @@
+use std::collections::VecDeque;
+
+use super::argument::Argument;
+use super::argument::HelpDetailSection;
+use super::argument_common::ArgumentCommon;
+use super::argument_common::ArgumentCommonBuilder;
+use super::errors::ArgError;
+use super::errors::error;
+use super::errors::OptionExt;
+use super::errors::DEFINITION_ERROR;
+use super::output_argument;
+use super::output_array;
+use super::output_indexed;
+use super::Settings;
+use super::json::json_string;
+
+pub struct ListArgument {
+  common: ArgumentCommon,
+  delimiter: String,
+}
+
+impl ListArgument {
+  #[allow(clippy::needless_return, clippy::redundant_field_names)]
+  pub fn new(args: &mut VecDeque<String>) -> Result<Self, ArgError> {
+    let mut common = ArgumentCommon::new_builder();
+    let mut delimiter = String::from(",");
+
+    loop {
+      match common.parse_arguments(args)?.as_deref() {
+        None => { break; }
+        Some("--delimiter") | Some("--delim") => {
+          delimiter = args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("delimiter must be provided after --delimiter"))?;
+        }
+        Some(other) => {
+          args.push_front(other.to_string());
+          break;
+        }
+      }
+    }
+
+    let common = common.build()?;
+
+    if delimiter.is_empty() {
+      return Err(error(DEFINITION_ERROR, format!("List argument {} can not have an empty delimiter", common.get_name())));
+    }
+
+    return Ok(ListArgument {
+      common: common,
+      delimiter: delimiter,
+    });
+  }
+
+  fn split(&self, values: &[String]) -> Vec<String> {
+    values.iter()
+        .filter(|value| !value.is_empty())
+        .flat_map(|value| value.split(self.delimiter.as_str()))
+        .map(|item| item.to_string())
+        .collect()
+  }
+}
+
+impl Argument for ListArgument {
+  fn get_common(&self) -> &ArgumentCommon {
+    &self.common
+  }
+
+  fn get_common_mut(&mut self) -> &mut ArgumentCommon {
+    &mut self.common
+  }
+
+  #[allow(clippy::needless_return)]
+  fn get_debug_info(&self) -> String {
+    return format!("type: List; {}; delimiter: '{}'", self.common.get_debug_info(), self.delimiter);
+  }
+
+  fn to_spec_json(&self) -> String {
+    self.common.to_spec_json("list", vec![("delimiter", json_string(&self.delimiter))])
+  }
+
+  fn get_help_details(&self) -> Vec<HelpDetailSection> {
+    vec![
+        HelpDetailSection::Text(self.get_description().clone().unwrap_or(String::from("No details available."))),
+        HelpDetailSection::Text(format!("Multiple values can be provided separated by '{}'.", self.delimiter)),
+    ]
+  }
+
+  /// Each value is split into the items of the list.
+  fn output_variables(&self, _settings: &Settings, values: &[String]) -> Vec<(String, Vec<String>)> {
+    vec![(self.get_name().clone(), self.split(values))]
+  }
+
+  fn output_values(&self, settings: &Settings, values: &[String]) {
+    let items = self.split(values);
+
+    if self.get_join().is_some() {
+      output_argument(settings, self.get_name(), items.join(self.get_join().as_ref().unwrap()), self.is_secret());
+    } else if settings.arrays || settings.shell.has_native_lists() {
+      output_array(settings, self.get_name(), &items, self.is_secret());
+    } else {
+      output_indexed(settings, self.get_name(), &items, self.is_secret());
+    }
+  }
+
+  fn writes_list(&self) -> bool {
+    self.get_join().is_none()
+  }
+
+  /// A list that got no value has no items, so it is written as an empty list.
+  fn output_missing(&self, settings: &Settings) {
+    self.output_values(settings, &[]);
+  }
+
+  fn consume(&self, settings: &Settings, arg: Option<String>, other_args: &mut VecDeque<String>) -> Result<Option<Vec<String>>, ArgError> {
+    self.consume_with_parser(
+      settings,
+      arg,
+      other_args,
+      &|_name, value: &String| Ok(value.clone()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::test_support::eval_in;
+  use super::super::test_support::parse_values;
+  use super::super::test_support::run;
+  use super::super::test_support::strings;
+
+  #[test]
+  fn declare_all_writes_an_empty_list() {
+    assert_eq!(run(&["--declare-all", "--no-arrays", "--list", "l", "--"]).0, "L='0'\n");
+
+    let (output, _) = run(&["--declare-all", "--list", "l", "--"]);
+    assert_eq!(eval_in("bash", &output, "set -u; echo \"${#L[@]}\""), "0\n");
+  }
+
+  #[test]
+  fn parse_returns_the_items_of_the_list() {
+    let definition = ["--list", "tags", "--delimiter", ","];
+    assert_eq!(parse_values(&definition, &["--tags", "a,b,c"]).unwrap()["TAGS"], strings(&["a", "b", "c"]));
+
+    let with_default = [&definition[..], &["--default", "x,y"]].concat();
+    assert_eq!(parse_values(&with_default, &[]).unwrap()["TAGS"], strings(&["x", "y"]));
+  }
+}
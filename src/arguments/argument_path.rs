@@ -0,0 +1,240 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::path::Path;
+
+use super::argument::Argument;
+use super::argument::HelpDetailSection;
+use super::argument_common::ArgumentCommon;
+use super::argument_common::ArgumentCommonBuilder;
+use super::output_debug;
+use super::errors::ArgError;
+use super::errors::error;
+use super::errors::DEFINITION_ERROR;
+use super::errors::USER_ERROR;
+use super::Settings;
+
+pub struct PathArgument {
+  common: ArgumentCommon,
+  must_exist: bool,
+  must_be_dir: bool,
+  must_be_file: bool,
+  readable: bool,
+  writable: bool,
+}
+
+impl PathArgument {
+  #[allow(clippy::needless_return, clippy::redundant_field_names)]
+  pub fn new(args: &mut VecDeque<String>) -> Result<Self, ArgError> {
+    let mut common = ArgumentCommon::new_builder();
+    let mut must_exist = false;
+    let mut must_be_dir = false;
+    let mut must_be_file = false;
+    let mut readable = false;
+    let mut writable = false;
+
+    loop {
+      match common.parse_arguments(args)?.as_deref() {
+        None => { break; }
+        Some("--must-exist") => {
+          must_exist = true;
+        }
+        Some("--must-be-dir") => {
+          must_be_dir = true;
+        }
+        Some("--must-be-file") => {
+          must_be_file = true;
+        }
+        Some("--readable") => {
+          readable = true;
+        }
+        Some("--writable") => {
+          writable = true;
+        }
+        Some(other) => {
+          args.push_front(other.to_string());
+          break;
+        }
+      }
+    }
+
+    let common = common.build()?;
+
+    if must_be_dir && must_be_file {
+      return Err(error(DEFINITION_ERROR, format!(
+          "Path argument {} can not use both --must-be-dir and --must-be-file",
+          common.get_name())));
+    }
+
+    return Ok(PathArgument {
+      common: common,
+      must_exist: must_exist || must_be_dir || must_be_file || readable,
+      must_be_dir: must_be_dir,
+      must_be_file: must_be_file,
+      readable: readable,
+      writable: writable,
+    });
+  }
+
+  #[allow(clippy::needless_return)]
+  fn parse_value(&self, settings: &Settings, name: &str, value: &str) -> Result<String, ArgError> {
+    let path = Path::new(value);
+
+    if self.must_exist && !path.exists() {
+      return Err(error(USER_ERROR, settings.messages.get("path_missing", &[("value", &value), ("name", &name)])));
+    }
+
+    if self.must_be_dir && !path.is_dir() {
+      return Err(error(USER_ERROR, settings.messages.get("path_not_directory", &[("value", &value), ("name", &name)])));
+    }
+
+    if self.must_be_file && !path.is_file() {
+      return Err(error(USER_ERROR, settings.messages.get("path_not_file", &[("value", &value), ("name", &name)])));
+    }
+
+    if self.readable && !is_readable(path) {
+      return Err(error(USER_ERROR, settings.messages.get("path_not_readable", &[("value", &value), ("name", &name)])));
+    }
+
+    if self.writable && !is_writable(settings, path) {
+      return Err(error(USER_ERROR, settings.messages.get("path_not_writable", &[("value", &value), ("name", &name)])));
+    }
+
+    return Ok(value.to_string());
+  }
+}
+
+impl Argument for PathArgument {
+  fn get_common(&self) -> &ArgumentCommon {
+    &self.common
+  }
+
+  fn get_common_mut(&mut self) -> &mut ArgumentCommon {
+    &mut self.common
+  }
+
+  #[allow(clippy::needless_return)]
+  fn get_debug_info(&self) -> String {
+    let mut description = format!("type: Path; {}", self.common.get_debug_info());
+    if self.must_be_dir {
+      description.push_str("; must be a directory");
+    } else if self.must_be_file {
+      description.push_str("; must be a file");
+    } else if self.must_exist {
+      description.push_str("; must exist");
+    }
+    if self.readable {
+      description.push_str("; readable");
+    }
+    if self.writable {
+      description.push_str("; writable");
+    }
+    return description;
+  }
+
+  fn to_spec_json(&self) -> String {
+    self.common.to_spec_json("path", vec![
+        ("must_exist", self.must_exist.to_string()),
+        ("must_be_dir", self.must_be_dir.to_string()),
+        ("must_be_file", self.must_be_file.to_string()),
+        ("readable", self.readable.to_string()),
+        ("writable", self.writable.to_string()),
+    ])
+  }
+
+  fn get_help_details(&self) -> Vec<HelpDetailSection> {
+    let mut lines = vec![
+        HelpDetailSection::Text(self.get_description().clone().unwrap_or(String::from("No details available."))),
+    ];
+
+    if self.must_be_dir {
+      lines.push(HelpDetailSection::Text(String::from("The path must be an existing directory.")));
+    } else if self.must_be_file {
+      lines.push(HelpDetailSection::Text(String::from("The path must be an existing file.")));
+    } else if self.must_exist {
+      lines.push(HelpDetailSection::Text(String::from("The path must already exist.")));
+    }
+
+    if self.readable && self.writable {
+      lines.push(HelpDetailSection::Text(String::from("The path must be readable and writable.")));
+    } else if self.readable {
+      lines.push(HelpDetailSection::Text(String::from("The path must be readable.")));
+    } else if self.writable {
+      lines.push(HelpDetailSection::Text(String::from("The path must be writable.")));
+    }
+
+    lines
+  }
+
+  /// The path checks depend on the file system when the script runs, so the default is checked when
+  /// it is used rather than when it is defined.
+  fn check_used_default(&self, settings: &Settings, default: &str) -> Result<(), ArgError> {
+    self.parse_value(settings, &format!("{} default", self.get_name()), default).map(|_| ())
+  }
+
+  fn consume(&self, settings: &Settings, arg: Option<String>, other_args: &mut VecDeque<String>) -> Result<Option<Vec<String>>, ArgError> {
+    self.consume_with_parser(
+      settings,
+      arg,
+      other_args,
+      &|name, value: &String| self.parse_value(settings, name, value))
+  }
+}
+
+/// Checks that a path can be read by actually opening it, or listing it for a directory.
+#[allow(clippy::needless_return)]
+fn is_readable(path: &Path) -> bool {
+  if path.is_dir() {
+    return std::fs::read_dir(path).is_ok();
+  }
+  return File::open(path).is_ok();
+}
+
+/// Checks that a path can be written. A regular file is opened for writing, which leaves its
+/// contents alone. Anything else only has its permissions checked, which can't tell whether this
+/// user in particular may write to it. A path that doesn't exist yet needs a writable directory to
+/// be created in.
+#[allow(clippy::needless_return)]
+fn is_writable(settings: &Settings, path: &Path) -> bool {
+  if path.is_file() {
+    return OpenOptions::new().write(true).open(path).is_ok();
+  }
+
+  if !path.exists() {
+    let parent = match path.parent() {
+      Some(parent) if !parent.as_os_str().is_empty() => parent,
+      _ => Path::new("."),
+    };
+    return parent.is_dir() && is_writable(settings, parent);
+  }
+
+  output_debug(settings, format!("Only the permissions of '{}' could be checked for writing", path.display()));
+  return std::fs::metadata(path).map(|metadata| !metadata.permissions().readonly()).unwrap_or(false);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::errors::ArgError;
+  use super::super::test_support::parse_values;
+  use super::super::test_support::strings;
+
+  #[test]
+  fn the_default_is_checked_when_it_is_used() {
+    let missing = std::env::temp_dir().join(format!("argparse-sh-missing-{}", std::process::id()));
+    let missing = missing.to_str().unwrap();
+    let definition = ["--path", "cfg", "--must-exist", "--default", missing];
+
+    assert_eq!(
+        parse_values(&definition, &[]),
+        Err(ArgError::User(format!("Path '{missing}' provided for argument CFG default does not exist"))));
+
+    let present = std::env::temp_dir();
+    let present = present.to_str().unwrap();
+    assert_eq!(parse_values(&definition, &["--cfg", present]).unwrap()["CFG"], strings(&[present]));
+
+    let directory = ["--path", "cfg", "--must-be-file", "--default", present];
+    assert_eq!(
+        parse_values(&directory, &[]),
+        Err(ArgError::User(format!("Path '{present}' provided for argument CFG default is not a file"))));
+  }
+}
@@ -1,26 +1,124 @@
+use regex::Regex;
 use std::collections::VecDeque;
 
 use super::argument::Argument;
+use super::argument::HelpDetailSection;
 use super::argument_common::ArgumentCommon;
 use super::argument_common::ArgumentCommonBuilder;
+use super::errors::ArgError;
+use super::errors::error;
+use super::errors::OptionExt;
+use super::errors::DEFINITION_ERROR;
+use super::errors::USER_ERROR;
+use super::json::json_optional_string;
+use super::Settings;
 
 pub struct StringArgument {
   common: ArgumentCommon,
+  non_empty: bool,
+  trim: bool,
+  lowercase: bool,
+  uppercase: bool,
+  pattern: Option<String>,
+  unanchored: bool,
+  regex: Option<Regex>,
 }
 
 impl StringArgument {
-  pub fn new(args: &mut VecDeque<String>) -> Self {
+  #[allow(clippy::needless_return, clippy::redundant_field_names)]
+  pub fn new(args: &mut VecDeque<String>) -> Result<Self, ArgError> {
     let mut common = ArgumentCommon::new_builder();
-    match common.parse_arguments(args) {
-      None => { }
-      Some(other) => {
-        args.push_front(other);
+    let mut non_empty = false;
+    let mut trim = false;
+    let mut lowercase = false;
+    let mut uppercase = false;
+    let mut pattern = None;
+    let mut unanchored = false;
+
+    loop {
+      match common.parse_arguments(args)?.as_deref() {
+        None => { break; }
+        Some("--non-empty") => {
+          non_empty = true;
+        }
+        Some("--allow-empty") => {
+          non_empty = false;
+        }
+        Some("--trim") => {
+          trim = true;
+        }
+        Some("--lowercase") => {
+          lowercase = true;
+        }
+        Some("--uppercase") => {
+          uppercase = true;
+        }
+        Some("--pattern") => {
+          pattern = Some(args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("regular expression must be provided after --pattern"))?);
+        }
+        Some("--unanchored") => {
+          unanchored = true;
+        }
+        Some(other) => {
+          args.push_front(other.to_string());
+          break;
+        }
       }
     }
 
-    return StringArgument {
-      common: common.build(),
-    };
+    let common = common.build()?;
+
+    if lowercase && uppercase {
+      return Err(error(DEFINITION_ERROR, format!("String argument {} can not use both --lowercase and --uppercase", common.get_name())));
+    }
+
+    if unanchored && pattern.is_none() {
+      return Err(error(DEFINITION_ERROR, format!("String argument {} can only use --unanchored with --pattern", common.get_name())));
+    }
+
+    // By default the pattern has to match the whole value, not just part of it.
+    let regex = pattern.as_ref().map(|pattern| {
+      let full_pattern = if unanchored { pattern.clone() } else { format!("^(?:{pattern})$") };
+      Regex::new(&full_pattern)
+          .ok_or_error(DEFINITION_ERROR, format!("Invalid pattern '{pattern}' provided for argument {}", common.get_name()))
+    }).transpose()?;
+
+    return Ok(StringArgument {
+      common: common,
+      non_empty: non_empty,
+      trim: trim,
+      lowercase: lowercase,
+      uppercase: uppercase,
+      pattern: pattern,
+      unanchored: unanchored,
+      regex: regex,
+    });
+  }
+
+  #[allow(clippy::needless_return)]
+  fn parse_value(&self, settings: &Settings, name: &str, value: &str, exit_code: i32) -> Result<String, ArgError> {
+    let value = if self.trim { value.trim_ascii() } else { value };
+
+    if self.non_empty && value.is_empty() {
+      return Err(error(exit_code, settings.messages.get("empty_value", &[("name", &name)])));
+    }
+
+    if self.regex.is_some() && !self.regex.as_ref().unwrap().is_match(value) {
+      return Err(error(exit_code, settings.messages.get("pattern_mismatch", &[
+          ("value", &value),
+          ("name", &name),
+          ("pattern", self.pattern.as_ref().unwrap()),
+      ])));
+    }
+
+    if self.lowercase {
+      return Ok(value.to_lowercase());
+    } else if self.uppercase {
+      return Ok(value.to_uppercase());
+    }
+
+    return Ok(value.to_string());
   }
 }
 
@@ -29,15 +127,95 @@ impl Argument for StringArgument {
     &self.common
   }
 
+  fn get_common_mut(&mut self) -> &mut ArgumentCommon {
+    &mut self.common
+  }
+
+  #[allow(clippy::needless_return, clippy::unnecessary_unwrap)]
   fn get_debug_info(&self) -> String {
-    return format!("type: String; {}", self.common.get_debug_info());
+    let mut description = format!("type: String; {}", self.common.get_debug_info());
+    if self.non_empty {
+      description.push_str("; non-empty");
+    }
+    if self.trim {
+      description.push_str("; trimmed");
+    }
+    if self.lowercase {
+      description.push_str("; lowercase");
+    }
+    if self.uppercase {
+      description.push_str("; uppercase");
+    }
+    if self.pattern.is_some() {
+      description.push_str(&format!(
+          "; pattern: {}{}",
+          self.pattern.as_ref().unwrap(),
+          if self.unanchored { " (unanchored)" } else { "" }));
+    }
+    return description;
+  }
+
+  fn to_spec_json(&self) -> String {
+    self.common.to_spec_json("string", vec![
+        ("non_empty", self.non_empty.to_string()),
+        ("trim", self.trim.to_string()),
+        ("lowercase", self.lowercase.to_string()),
+        ("uppercase", self.uppercase.to_string()),
+        ("pattern", json_optional_string(&self.pattern)),
+        ("unanchored", self.unanchored.to_string()),
+    ])
   }
 
-  fn consume(&self, arg: Option<String>, other_args: &mut VecDeque<String>) -> Option<String> {
+  #[allow(clippy::unnecessary_unwrap)]
+  fn get_help_details(&self) -> Vec<HelpDetailSection> {
+    let mut lines = vec![
+        HelpDetailSection::Text(self.get_description().clone().unwrap_or(String::from("No details available."))),
+    ];
+
+    if self.non_empty {
+      lines.push(HelpDetailSection::Text(String::from("The value can not be empty.")));
+    }
+
+    if self.trim {
+      lines.push(HelpDetailSection::Text(String::from("Leading and trailing whitespace is removed from the value.")));
+    }
+
+    if self.pattern.is_some() && self.unanchored {
+      lines.push(HelpDetailSection::Text(format!(
+          "The value must contain a match for the regular expression '{}'.",
+          self.pattern.as_ref().unwrap())));
+    } else if self.pattern.is_some() {
+      lines.push(HelpDetailSection::Text(format!(
+          "The whole value must match the regular expression '{}'.",
+          self.pattern.as_ref().unwrap())));
+    }
+
+    if self.lowercase {
+      lines.push(HelpDetailSection::Text(String::from("The value is converted to lowercase.")));
+    } else if self.uppercase {
+      lines.push(HelpDetailSection::Text(String::from("The value is converted to uppercase.")));
+    }
+
+    lines
+  }
+
+  #[allow(clippy::needless_return)]
+  fn check_default(&self, settings: &Settings) -> Result<(), ArgError> {
+    if self.get_default().is_some() {
+      self.parse_value(settings, &format!("{} default", self.get_name()), self.get_default().as_ref().unwrap(), DEFINITION_ERROR)?;
+    }
+    return Ok(());
+  }
+
+  fn default_output_value(&self, settings: &Settings, default: &str) -> String {
+    self.parse_value(settings, self.get_name(), default, DEFINITION_ERROR).unwrap()
+  }
+
+  fn consume(&self, settings: &Settings, arg: Option<String>, other_args: &mut VecDeque<String>) -> Result<Option<Vec<String>>, ArgError> {
     self.consume_with_parser(
+      settings,
       arg,
       other_args,
-      |_name, value: &String| value.clone())
+      &|name, value: &String| self.parse_value(settings, name, value, USER_ERROR))
   }
 }
-
@@ -0,0 +1,215 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use super::argument::Argument;
+use super::argument::HelpDetailSection;
+use super::argument_common::ArgumentCommon;
+use super::argument_common::ArgumentCommonBuilder;
+use super::errors::ArgError;
+use super::errors::error;
+use super::errors::OptionExt;
+use super::errors::DEFINITION_ERROR;
+use super::errors::USER_ERROR;
+use super::json::json_optional;
+use super::output_debug;
+use super::debug_value;
+use super::Settings;
+
+pub struct UintArgument {
+  common: ArgumentCommon,
+  min: Option<u64>,
+  max: Option<u64>,
+  clamp: bool,
+}
+
+impl UintArgument {
+  #[allow(clippy::needless_return, clippy::redundant_field_names, clippy::unnecessary_unwrap)]
+  pub fn new(args: &mut VecDeque<String>) -> Result<Self, ArgError> {
+    let mut common = ArgumentCommon::new_builder();
+    let mut min = None;
+    let mut max = None;
+    let mut clamp = false;
+
+    loop {
+      match common.parse_arguments(args)?.as_deref() {
+        None => { break; }
+        Some("--min") => {
+          let value = args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("minimum value must be provided after --min"))?;
+          min = Some(parse_bound("--min", &value)?);
+        }
+        Some("--max") => {
+          let value = args.pop_front()
+              .ok_or_error(DEFINITION_ERROR, String::from("maximum value must be provided after --max"))?;
+          max = Some(parse_bound("--max", &value)?);
+        }
+        Some("--clamp") => {
+          clamp = true;
+        }
+        Some(other) => {
+          args.push_front(other.to_string());
+          break;
+        }
+      }
+    }
+
+    let common = common.build()?;
+
+    if clamp && min.is_none() && max.is_none() {
+      return Err(error(DEFINITION_ERROR, format!("--clamp requires --min or --max for argument {}", common.get_name())));
+    }
+
+    if min.is_some() && max.is_some() && min.unwrap() > max.unwrap() {
+      return Err(error(DEFINITION_ERROR, format!(
+          "Unsigned argument {} has a minimum of {} which is above its maximum of {}",
+          common.get_name(),
+          min.unwrap(),
+          max.unwrap())));
+    }
+
+    return Ok(UintArgument {
+      common: common,
+      min: min,
+      max: max,
+      clamp: clamp,
+    });
+  }
+
+  /// Parses and checks a value. With `clamp`, values outside of the range are moved to the nearest
+  /// bound instead of failing.
+  #[allow(clippy::needless_return, clippy::unnecessary_unwrap)]
+  fn parse_value(&self, settings: &Settings, name: &str, value: &str, exit_code: i32, clamp: bool) -> Result<String, ArgError> {
+    let mut parsed = u64::from_str(value)
+        .ok_or_error(exit_code, settings.messages.get("invalid_unsigned", &[("name", &name), ("value", &value)]))?;
+
+    if self.min.is_some() && parsed < self.min.unwrap() && clamp {
+      output_debug(settings, format!(
+          "Clamped value {} for {name} to the minimum of {}",
+          debug_value(self.is_secret(), parsed),
+          debug_value(self.is_secret(), self.min.unwrap())));
+      parsed = self.min.unwrap();
+    } else if self.min.is_some() && parsed < self.min.unwrap() {
+      return Err(error(exit_code, settings.messages.get("below_minimum", &[("value", &parsed), ("name", &name), ("min", &self.min.unwrap())])));
+    }
+
+    if self.max.is_some() && parsed > self.max.unwrap() && clamp {
+      output_debug(settings, format!(
+          "Clamped value {} for {name} to the maximum of {}",
+          debug_value(self.is_secret(), parsed),
+          debug_value(self.is_secret(), self.max.unwrap())));
+      parsed = self.max.unwrap();
+    } else if self.max.is_some() && parsed > self.max.unwrap() {
+      return Err(error(exit_code, settings.messages.get("above_maximum", &[("value", &parsed), ("name", &name), ("max", &self.max.unwrap())])));
+    }
+
+    return Ok(parsed.to_string());
+  }
+}
+
+impl Argument for UintArgument {
+  fn get_common(&self) -> &ArgumentCommon {
+    &self.common
+  }
+
+  fn get_common_mut(&mut self) -> &mut ArgumentCommon {
+    &mut self.common
+  }
+
+  #[allow(clippy::needless_return, clippy::unnecessary_unwrap)]
+  fn get_debug_info(&self) -> String {
+    let mut description = format!("type: Unsigned; {}", self.common.get_debug_info());
+    if self.min.is_some() {
+      description.push_str(&format!("; min: {}", self.min.unwrap()));
+    }
+    if self.max.is_some() {
+      description.push_str(&format!("; max: {}", self.max.unwrap()));
+    }
+    if self.clamp {
+      description.push_str("; clamped to range");
+    }
+    return description;
+  }
+
+  fn to_spec_json(&self) -> String {
+    self.common.to_spec_json("unsigned", vec![
+        ("min", json_optional(&self.min)),
+        ("max", json_optional(&self.max)),
+        ("clamp", self.clamp.to_string()),
+    ])
+  }
+
+  fn get_help_details(&self) -> Vec<HelpDetailSection> {
+    let mut lines = vec![
+        HelpDetailSection::Text(self.get_description().clone().unwrap_or(String::from("No details available."))),
+    ];
+
+    match (self.min, self.max) {
+      (Some(min), Some(max)) => {
+        lines.push(HelpDetailSection::Text(format!("The value must be between {min} and {max}, inclusive.")));
+      }
+      (Some(min), None) => {
+        lines.push(HelpDetailSection::Text(format!("The value must be at least {min}.")));
+      }
+      (None, Some(max)) => {
+        lines.push(HelpDetailSection::Text(format!("The value must be at most {max}.")));
+      }
+      (None, None) => {}
+    }
+
+    if self.clamp {
+      lines.push(HelpDetailSection::Text(String::from("Values outside of the range are changed to the nearest bound.")));
+    }
+
+    lines
+  }
+
+  #[allow(clippy::needless_return)]
+  fn check_default(&self, settings: &Settings) -> Result<(), ArgError> {
+    if self.get_default().is_some() {
+      self.parse_value(settings, &format!("{} default", self.get_name()), self.get_default().as_ref().unwrap(), DEFINITION_ERROR, false)?;
+    }
+    return Ok(());
+  }
+
+  fn default_output_value(&self, settings: &Settings, default: &str) -> String {
+    self.parse_value(settings, self.get_name(), default, DEFINITION_ERROR, false).unwrap()
+  }
+
+  fn consume(&self, settings: &Settings, arg: Option<String>, other_args: &mut VecDeque<String>) -> Result<Option<Vec<String>>, ArgError> {
+    self.consume_with_parser(
+      settings,
+      arg,
+      other_args,
+      &|name, value: &String| self.parse_value(settings, name, value, USER_ERROR, self.clamp))
+  }
+}
+
+/// Parses the value of `--min` or `--max`. Both bounds have to be non-negative, like the values they
+/// limit.
+fn parse_bound(flag: &str, value: &str) -> Result<u64, ArgError> {
+  u64::from_str(value)
+      .ok_or_error(DEFINITION_ERROR, format!("Non-negative integer value required for {flag}, got '{value}'"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::super::test_support::parse_values;
+  use super::super::ArgError;
+
+  #[test]
+  fn default_is_written_like_a_value() {
+    assert_eq!(parse_values(&["--uint", "n", "--default", "007"], &[]).unwrap()["N"], ["7"]);
+    assert_eq!(parse_values(&["--uint", "n", "--default", "007"], &["--n", "007"]).unwrap()["N"], ["7"]);
+  }
+
+  #[test]
+  fn bounds_must_be_non_negative() {
+    for flag in ["--min", "--max"] {
+      for value in ["-1", "x"] {
+        assert_eq!(
+            parse_values(&["--uint", "n", flag, value], &[]),
+            Err(ArgError::Definition(format!("Non-negative integer value required for {flag}, got '{value}'"))));
+      }
+    }
+  }
+}
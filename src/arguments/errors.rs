@@ -1,34 +1,174 @@
+use super::json::json_object;
+use super::json::json_string;
+use super::json::json_string_array;
+use super::messages::Messages;
+use super::shell::Shell;
+
 pub const HELP_ERROR: i32 = 1;
 pub const DEFINITION_ERROR: i32 = 2;
 pub const USER_ERROR: i32 = 3;
 
-pub fn error<S: AsRef<str>>(exit_code: i32, message: S) {
-  println!("echo \"\"");
-  println!("echo \"!!! ArgParse-sh Error: {} !!!\"", message.as_ref());
-  println!("echo \"\"");
-  println!("( exit {exit_code} )");
-  std::process::exit(exit_code);
+/// An error returned by the library API, instead of being written out as shell code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgError {
+  /// The argument definitions are invalid.
+  Definition(String),
+  /// The values given by the user are invalid. When several problems are found at once, each one is
+  /// on its own line.
+  User(String),
+  /// The user asked for the help text or the version, which the caller should show instead.
+  Help,
+}
+
+impl std::fmt::Display for ArgError {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      ArgError::Definition(message) => write!(f, "{message}"),
+      ArgError::User(message) => write!(f, "{message}"),
+      ArgError::Help => write!(f, "help requested"),
+    }
+  }
+}
+
+impl std::error::Error for ArgError {}
+
+/// How `write_error` writes an error. `handle_all_arguments` fills this in as it reads the
+/// definitions, so an error raised before all of them are read still uses the settings seen so far.
+#[derive(Clone, Debug, Default)]
+pub struct ErrorFormat {
+  /// The shell that the error is written for.
+  pub(crate) shell: Shell,
+  /// Whether errors are written as plain text to stderr instead of as shell commands. This is used
+  /// when the output is not meant to be evaluated by a shell.
+  pub(crate) plain: bool,
+  /// Whether errors leave out the message and only report the exit status.
+  pub(crate) quiet: bool,
+  /// Whether error messages are written as a JSON object instead of a banner.
+  pub(crate) json: bool,
+  /// The messages used for the error banner.
+  pub(crate) messages: Messages,
+}
+
+/// Builds the error for an exit code. Errors are returned up through `handle_all_arguments`, and the
+/// caller writes them out with `write_error`.
+pub fn error<S: AsRef<str>>(exit_code: i32, message: S) -> ArgError {
+  match exit_code {
+    HELP_ERROR => ArgError::Help,
+    DEFINITION_ERROR => ArgError::Definition(message.as_ref().to_string()),
+    _ => ArgError::User(message.as_ref().to_string()),
+  }
+}
+
+impl ArgError {
+  /// Gets the exit status used for this error.
+  pub fn exit_code(&self) -> i32 {
+    match self {
+      ArgError::Definition(_) => DEFINITION_ERROR,
+      ArgError::User(_) => USER_ERROR,
+      ArgError::Help => HELP_ERROR,
+    }
+  }
+}
+
+/// Writes an error out as shell code (or plain text for `--output-format nul`) and returns the exit
+/// status to use. Help has already been written, so only its exit status is. The JSON format always
+/// lists every message in `errors`, and also has the first one in `error`.
+#[allow(clippy::needless_return)]
+pub fn write_error(error: &ArgError, format: &ErrorFormat) -> i32 {
+  let exit_code = error.exit_code();
+  let errors = match error {
+    ArgError::Definition(message) | ArgError::User(message) => message.lines().map(String::from).collect::<Vec<String>>(),
+    ArgError::Help => {
+      outln!("{}", format.shell.exit_status(exit_code));
+      return exit_code;
+    }
+  };
+
+  let quiet = format.quiet;
+  let json = format.json;
+  let json_message = json_object(vec![
+      ("error", json_string(errors.first().map(|message| message.as_str()).unwrap_or(""))),
+      ("errors", json_string_array(&errors)),
+      ("code", exit_code.to_string())]);
+
+  if format.plain {
+    if !quiet && json {
+      eprintln!("{json_message}");
+    } else if !quiet {
+      for message in errors.iter() {
+        eprintln!("ArgParse-sh Error: {message}");
+      }
+    }
+    return exit_code;
+  }
+
+  if !quiet && json {
+    outln!("{}", format.shell.print_text(json_message));
+  } else if !quiet {
+    outln!("echo \"\"");
+    for message in errors.iter() {
+      outln!("echo {}", format.shell.quote(format.messages.get("error_banner", &[("message", &message)])));
+    }
+    outln!("echo \"\"");
+  }
+  outln!("{}", format.shell.exit_status(exit_code));
+  return exit_code;
 }
 
 pub trait OptionExt<T> {
-  fn unwrap_or_error(self, exit_code: i32, message: String) -> T;
+  fn ok_or_error(self, exit_code: i32, message: String) -> Result<T, ArgError>;
 }
 
 impl<T> OptionExt<T> for Option<T> {
-  fn unwrap_or_error(self, exit_code: i32, message: String) -> T {
-    if self.is_none() {
-      error(exit_code, message);
-    }
-    return self.unwrap();
+  fn ok_or_error(self, exit_code: i32, message: String) -> Result<T, ArgError> {
+    self.ok_or_else(|| error(exit_code, message))
   }
 }
 
 impl<T, E: std::fmt::Debug> OptionExt<T> for Result<T, E> {
-  fn unwrap_or_error(self, exit_code: i32, message: String) -> T {
-    if self.is_err() {
-      error(exit_code, message);
-    }
-    return self.unwrap();
+  fn ok_or_error(self, exit_code: i32, message: String) -> Result<T, ArgError> {
+    self.map_err(|_| error(exit_code, message))
   }
 }
 
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::test_support::eval_in;
+  use super::super::test_support::run;
+
+  #[test]
+  fn error_messages_are_quoted() {
+    let (output, exit_code) = run(&["--integer", "count", "--", "--count", "$(echo injected)'\"`"]);
+
+    assert_eq!(exit_code, USER_ERROR);
+    assert_eq!(
+        eval_in("sh", &output, "echo \"status $?\""),
+        "\n!!! ArgParse-sh Error: Non-integer value '$(echo injected)'\"`' provided for argument COUNT !!!\n\nstatus 3\n");
+  }
+
+  #[test]
+  fn json_errors_always_have_an_errors_array() {
+    let (output, _) = run(&["--error-format", "json", "--integer", "count", "--", "--count", "many"]);
+    assert_eq!(
+        eval_in("sh", &output, ""),
+        "{\"error\": \"Non-integer value 'many' provided for argument COUNT\", \"errors\": [\"Non-integer value 'many' provided for argument COUNT\"], \"code\": 3}\n");
+
+    let (output, _) = run(&["--error-format", "json", "--string", "a", "--required", "--string", "b", "--required", "--"]);
+    let printed = eval_in("sh", &output, "");
+    assert!(printed.starts_with("{\"error\": \"Value for argument A is missing\", \"errors\": [\"Value for argument A is missing\", \""));
+  }
+
+  #[test]
+  fn errors_are_written_with_the_format_they_are_given() {
+    let error = ArgError::User(String::from("bad value"));
+    let json = ErrorFormat { json: true, ..ErrorFormat::default() };
+
+    super::super::capture_output();
+    assert_eq!(write_error(&error, &json), USER_ERROR);
+    assert_eq!(write_error(&error, &ErrorFormat::default()), USER_ERROR);
+    assert_eq!(
+        eval_in("sh", &super::super::take_captured_output(), ""),
+        "{\"error\": \"bad value\", \"errors\": [\"bad value\"], \"code\": 3}\n\n!!! ArgParse-sh Error: bad value !!!\n\n");
+  }
+}
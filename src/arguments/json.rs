@@ -0,0 +1,71 @@
+/// Writes a string as a quoted JSON string, escaping quotes, backslashes, and control characters.
+#[allow(clippy::needless_return)]
+pub fn json_string<S: AsRef<str>>(value: S) -> String {
+  let mut result = String::from("\"");
+
+  for c in value.as_ref().chars() {
+    match c {
+      '"' => result.push_str("\\\""),
+      '\\' => result.push_str("\\\\"),
+      '\n' => result.push_str("\\n"),
+      '\r' => result.push_str("\\r"),
+      '\t' => result.push_str("\\t"),
+      c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+      c => result.push(c),
+    }
+  }
+
+  result.push('"');
+  return result;
+}
+
+/// Writes an optional value as a JSON string, or `null` when it is absent.
+pub fn json_optional_string(value: &Option<String>) -> String {
+  match value {
+    None => String::from("null"),
+    Some(value) => json_string(value),
+  }
+}
+
+/// Writes an optional number using its `Display` form, or `null` when it is absent. JSON has no way
+/// to write NaN or an infinity, so those are `null` as well.
+pub fn json_optional<V: std::fmt::Display>(value: &Option<V>) -> String {
+  match value {
+    Some(value) if value.to_string().parse::<f64>().is_ok_and(|number| number.is_finite()) => value.to_string(),
+    _ => String::from("null"),
+  }
+}
+
+pub fn json_string_array(values: &[String]) -> String {
+  format!("[{}]", values.iter().map(json_string).collect::<Vec<String>>().join(", "))
+}
+
+/// Writes a JSON object from a list of keys and already-encoded values.
+pub fn json_object(fields: Vec<(&str, String)>) -> String {
+  format!(
+      "{{{}}}",
+      fields.iter()
+          .map(|(key, value)| format!("{}: {value}", json_string(key)))
+          .collect::<Vec<String>>()
+          .join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn optional_numbers() {
+    assert_eq!(json_optional(&Some(-3_i64)), "-3");
+    assert_eq!(json_optional(&Some(1.5_f64)), "1.5");
+    assert_eq!(json_optional::<u64>(&None), "null");
+    assert_eq!(json_optional(&Some(f64::NAN)), "null");
+    assert_eq!(json_optional(&Some(f64::INFINITY)), "null");
+    assert_eq!(json_optional(&Some(f64::NEG_INFINITY)), "null");
+  }
+
+  #[test]
+  fn strings_are_escaped() {
+    assert_eq!(json_string("a \"b\" \\ c\n\u{1}"), "\"a \\\"b\\\" \\\\ c\\n\\u0001\"");
+  }
+}
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// The built-in English text for every message shown to the user of a script. Placeholders in braces
+/// are filled in when the message is used. Any of these can be replaced with `--messages-file`.
+const DEFAULT_MESSAGES: [(&str, &str); 41] = [
+  ("error_banner", "!!! ArgParse-sh Error: {message} !!!"),
+  ("no_value", "No value provided for argument {name}"),
+  ("multiple_values", "Multiple values found for argument {name}"),
+  ("missing_value", "Value for argument {name} is missing"),
+  ("missing_catch_all", "At least one value must be provided for catch-all argument {name}"),
+  ("too_few_values", "Argument {name} requires at least {min} values, got {count}"),
+  ("too_many_values", "Argument {name} allows at most {max} values, got {count}"),
+  ("mutex", "Only one of {flags} can be provided"),
+  ("one_of", "At least one of {flags} must be provided"),
+  ("conflict", "{flag} can not be used with {other}"),
+  ("extra_argument", "Extra argument \"{value}\" passed and no catch-all argument found"),
+  ("did_you_mean", "Extra argument \"{value}\" passed and no catch-all argument found. Did you mean {flag}?"),
+  ("ambiguous_flag", "Flag {flag} is ambiguous; it could be any of {options}"),
+  ("response_file", "Unable to read response file '{path}'"),
+  ("stdin_reused", "Standard input was already read; it can not also be used for {name}"),
+  ("stdin_unreadable", "Unable to read standard input for {name}"),
+  ("invalid_integer", "Non-integer value '{value}' provided for argument {name}"),
+  ("invalid_unsigned", "Non-negative integer value required for argument {name}, got '{value}'"),
+  ("invalid_number", "Non-numeric value '{value}' provided for argument {name}"),
+  ("not_finite", "Value '{value}' provided for argument {name} must be a finite number"),
+  ("below_minimum", "Value '{value}' for {name} is below the minimum of {min}"),
+  ("above_maximum", "Value '{value}' for {name} is above the maximum of {max}"),
+  ("not_multiple", "Value {value} for {name} must be a multiple of {step}"),
+  ("invalid_boolean", "Non-boolean value '{value}' provided for argument {name}"),
+  ("negative_flag_value", "Boolean argument {name} does not support '{flag}=<value>' syntax"),
+  ("unknown_choice", "Value \"{value}\" not recognized for argument {name}"),
+  ("ambiguous_choice", "Value \"{value}\" is ambiguous for argument {name}; it could be {options}"),
+  ("empty_value", "Empty value provided for argument {name}"),
+  ("pattern_mismatch", "Value '{value}' provided for argument {name} does not match the pattern '{pattern}'"),
+  ("path_missing", "Path '{value}' provided for argument {name} does not exist"),
+  ("path_not_directory", "Path '{value}' provided for argument {name} is not a directory"),
+  ("path_not_file", "Path '{value}' provided for argument {name} is not a file"),
+  ("path_not_readable", "Path '{value}' provided for argument {name} is not readable"),
+  ("path_not_writable", "Path '{value}' provided for argument {name} is not writable"),
+  ("invalid_bytes", "Non-numeric byte size '{value}' provided for argument {name}"),
+  ("unknown_byte_suffix", "Unrecognized suffix '{suffix}' in byte size '{value}' provided for argument {name}"),
+  ("bytes_too_large", "Byte size '{value}' provided for argument {name} is too large"),
+  ("empty_duration", "Empty duration provided for argument {name}"),
+  ("unknown_duration_unit", "Unrecognized unit '{unit}' in duration '{value}' provided for argument {name}"),
+  ("invalid_duration", "Invalid duration '{value}' provided for argument {name}"),
+  ("duration_too_large", "Duration '{value}' provided for argument {name} is too large"),
+];
+
+/// Checks whether a key names one of the built-in messages.
+pub fn is_message_key(key: &str) -> bool {
+  DEFAULT_MESSAGES.iter().any(|(name, _)| *name == key)
+}
+
+/// The messages shown to the user of a script: the defaults above, with any loaded from
+/// `--messages-file` taking their place.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Messages {
+  overrides: HashMap<String, String>,
+}
+
+impl Messages {
+  #[allow(clippy::redundant_field_names)]
+  pub fn new(overrides: HashMap<String, String>) -> Messages {
+    Messages { overrides: overrides }
+  }
+
+  /// Gets the text for a message, with each `{placeholder}` replaced by its value. The text is
+  /// scanned once, so placeholders that appear inside a value are left alone.
+  #[allow(clippy::needless_return)]
+  pub fn get(&self, key: &str, values: &[(&str, &dyn Display)]) -> String {
+    let template = self.overrides.get(key)
+        .map(|text| text.as_str())
+        .or_else(|| DEFAULT_MESSAGES.iter().find(|(name, _)| *name == key).map(|(_, text)| *text))
+        .unwrap();
+    return fill_placeholders(template, values);
+  }
+}
+
+#[allow(clippy::needless_return, clippy::unnecessary_unwrap)]
+fn fill_placeholders(template: &str, values: &[(&str, &dyn Display)]) -> String {
+
+  let mut text = String::new();
+  let mut rest = template;
+  while let Some(start) = rest.find('{') {
+    text.push_str(&rest[..start]);
+    rest = &rest[start..];
+
+    let value = rest.find('}')
+        .and_then(|end| values.iter().find(|(name, _)| *name == &rest[1..end]).map(|(_, value)| (end, value)));
+    if value.is_some() {
+      let (end, value) = value.unwrap();
+      text.push_str(&value.to_string());
+      rest = &rest[end + 1..];
+    } else {
+      text.push('{');
+      rest = &rest[1..];
+    }
+  }
+  text.push_str(rest);
+
+  return text;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn placeholders_in_values_are_not_expanded() {
+    let messages = Messages::default();
+    assert_eq!(
+        messages.get("extra_argument", &[("value", &"{flag}"), ("flag", &"--x")]),
+        "Extra argument \"{flag}\" passed and no catch-all argument found");
+    assert_eq!(
+        messages.get("did_you_mean", &[("value", &"{value}"), ("flag", &"{value}")]),
+        "Extra argument \"{value}\" passed and no catch-all argument found. Did you mean {value}?");
+  }
+
+  #[test]
+  fn unknown_placeholders_are_kept() {
+    let messages = Messages::new(HashMap::from([(String::from("extra_argument"), String::from("{ {other} {value}} {value"))]));
+    assert_eq!(messages.get("extra_argument", &[("value", &"x")]), "{ {other} x} {value");
+  }
+}
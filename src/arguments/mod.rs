@@ -2,54 +2,141 @@ extern crate termsize;
 
 use regex::Regex;
 use std::collections::HashMap;
+use std::cell::Cell;
 use std::collections::VecDeque;
 use textwrap::fill;
 use textwrap::Options;
 use unicode_width::UnicodeWidthStr;
 
+/// Writes a line of output, like `println!`. Going through `write_output` lets tests capture it.
+macro_rules! outln {
+  () => {
+    $crate::arguments::write_output(String::from("\n"))
+  };
+  ($($arg:tt)*) => {
+    $crate::arguments::write_output(format!("{}\n", format_args!($($arg)*)))
+  };
+}
+
 mod errors;
 mod argument;
 mod argument_boolean;
+mod argument_bytes;
 mod argument_choice;
 mod argument_common;
+mod argument_count;
+mod argument_duration;
 mod argument_float;
 mod argument_integer;
+mod argument_list;
+mod argument_path;
 mod argument_string;
+mod argument_uint;
+mod json;
+mod messages;
+mod shell;
+#[cfg(test)]
+mod test_support;
 
+use argument_common::NameStyle;
 use errors::*;
 
+pub use errors::write_error;
+pub use errors::ArgError;
+pub use errors::ErrorFormat;
+use messages::Messages;
+use shell::Scope;
+use shell::Shell;
+
 struct Settings {
   arguments: Vec<Box<dyn argument::Argument>>,
   prefix: Option<String>,
   auto_help: bool,
-  export: bool,
+  auto_version: bool,
+  scope: Scope,
   debug: bool,
   program_name: Option<String>,
+  program_version: Option<String>,
   program_summary: Option<String>,
   program_description: Option<String>,
   remaining_args: Vec<String>,
   columns: usize,
   help_function: Option<String>,
+  arrays: bool,
+  shell: Shell,
+  completion: Option<String>,
+  export_spec: bool,
+  print_spec: bool,
+  allow_abbrev: bool,
+  help_format: String,
+  groups: Vec<(usize, String)>,
+  as_array: bool,
+  output_format: String,
+  response_files: bool,
+  validate_only: bool,
+  declare_all: bool,
+  skip_unset: bool,
+  no_pager: bool,
+  no_tty: bool,
+  pad_indices: bool,
+  pager_var: Option<String>,
+  output_array: Option<String>,
+  rest_string: Option<String>,
+  emit_provided: Option<String>,
+  skipped_short_flags: Vec<(String, String)>,
+  messages: Messages,
+  /// Whether a `--stdin-dash` argument has already consumed standard input.
+  stdin_read: Cell<bool>,
 }
 
-fn parse_settings(args: Vec<String>) -> Settings {
+#[allow(clippy::needless_return, clippy::redundant_field_names, clippy::unnecessary_unwrap)]
+fn parse_settings(args: Vec<String>, error_format: &mut ErrorFormat) -> Result<Settings, ArgError> {
   let mut args = VecDeque::from(args);
   args.pop_front();
 
   let mut arguments: Vec<Box<dyn argument::Argument>> = Vec::new();
   let mut prefix = None;
   let mut auto_help = false;
+  let mut auto_version = false;
   let mut export = false;
+  let mut local = false;
   let mut debug = false;
   let mut program_name = None;
+  let mut program_version = None;
   let mut program_summary = None;
   let mut program_description = None;
   let mut help_function = None;
+  let mut arrays = true;
+  let mut shell = Shell::Posix;
+  let mut completion = None;
+  let mut export_spec = false;
+  let mut print_spec = false;
+  let mut allow_abbrev = false;
+  let mut help_format = String::from("text");
+  let mut groups = Vec::new();
+  let mut as_array = false;
+  let mut output_format = String::from("shell");
+  let mut response_files = false;
+  let mut validate_only = false;
+  let mut declare_all = false;
+  let mut skip_unset = false;
+  let mut raw_names = false;
+  let mut no_pager = false;
+  let mut no_tty = false;
+  let mut pad_indices = false;
+  let mut env_import = false;
+  let mut pager_var = None;
+  let mut output_array = None;
+  let mut rest_string = None;
+  let mut emit_provided = None;
+  let mut auto_short = false;
+  let mut name_style = NameStyle::SnakeUpper;
+  let mut name_case_given = false;
+  let mut name_style_given = false;
+  let mut messages = Messages::default();
 
-  let mut columns = match termsize::get() {
-    None => 80_usize,
-    Some(size) => size.cols as usize,
-  };
+  let mut columns = None;
+  let mut max_width = None;
 
   loop {
     match args.pop_front().as_deref() {
@@ -57,236 +144,1261 @@ fn parse_settings(args: Vec<String>) -> Settings {
         break;
       }
       Some("--boolean") | Some("--bool") => {
-        arguments.push(Box::new(argument_boolean::BooleanArgument::new(&mut args)));
+        arguments.push(Box::new(argument_boolean::BooleanArgument::new(&mut args)?));
       }
       Some("--integer") | Some("--int") => {
-        arguments.push(Box::new(argument_integer::IntegerArgument::new(&mut args)));
+        arguments.push(Box::new(argument_integer::IntegerArgument::new(&mut args)?));
+      }
+      Some("--uint") | Some("--unsigned") => {
+        arguments.push(Box::new(argument_uint::UintArgument::new(&mut args)?));
       }
       Some("--float") | Some("--number") => {
-        arguments.push(Box::new(argument_float::FloatArgument::new(&mut args)));
+        arguments.push(Box::new(argument_float::FloatArgument::new(&mut args)?));
+      }
+      Some("--bytes") => {
+        arguments.push(Box::new(argument_bytes::BytesArgument::new(&mut args)?));
+      }
+      Some("--count") => {
+        arguments.push(Box::new(argument_count::CountArgument::new(&mut args)?));
+      }
+      Some("--duration") => {
+        arguments.push(Box::new(argument_duration::DurationArgument::new(&mut args)?));
       }
       Some("--string") | Some("--str") => {
-        arguments.push(Box::new(argument_string::StringArgument::new(&mut args)));
+        arguments.push(Box::new(argument_string::StringArgument::new(&mut args)?));
+      }
+      Some("--list") => {
+        arguments.push(Box::new(argument_list::ListArgument::new(&mut args)?));
+      }
+      Some("--path") | Some("--file") => {
+        arguments.push(Box::new(argument_path::PathArgument::new(&mut args)?));
       }
       Some("--choice") | Some("--pick") => {
-        arguments.push(Box::new(argument_choice::ChoiceArgument::new(&mut args)));
+        arguments.push(Box::new(argument_choice::ChoiceArgument::new(&mut args)?));
       }
       Some("--autohelp") | Some("--auto-help") => {
         auto_help = true;
       }
+      Some("--autoversion") | Some("--auto-version") => {
+        auto_version = true;
+      }
       Some("--help-function") => {
         help_function = Some(args.pop_front()
-            .unwrap_or_error(DEFINITION_ERROR, String::from("help function name must be provided after --help-function"))
+            .ok_or_error(DEFINITION_ERROR, String::from("help function name must be provided after --help-function"))?
             .to_string());
       }
       Some("--columns") | Some("--cols") => {
         let value = args.pop_front()
-            .unwrap_or_error(DEFINITION_ERROR, String::from("number of columns must be provided after --columns or --cols"));
-        columns = value
+            .ok_or_error(DEFINITION_ERROR, String::from("number of columns must be provided after --columns or --cols"))?;
+        columns = Some(value
+            .parse::<usize>()
+            .ok_or_error(DEFINITION_ERROR, format!("Non-numeric value '{value}' provided for number of columns"))?);
+      }
+      Some("--max-width") => {
+        let value = args.pop_front()
+            .ok_or_error(DEFINITION_ERROR, String::from("number of columns must be provided after --max-width"))?;
+        max_width = Some(value
             .parse::<usize>()
-            .unwrap_or_error(DEFINITION_ERROR, format!("Non-numeric value '{value}' provided for number of columns"))
+            .ok_or_error(DEFINITION_ERROR, format!("Non-numeric value '{value}' provided for --max-width"))?);
+        // Help text is indented by up to 15 columns, so anything narrower leaves no room for words.
+        if max_width.unwrap() < 20 {
+          return Err(error(DEFINITION_ERROR, format!("Value '{value}' provided for --max-width must be at least 20")));
+        }
       }
       Some("--program-name") => {
         program_name = Some(args.pop_front()
-            .unwrap_or_error(DEFINITION_ERROR, String::from("program name prefix must be provided after --program-name"))
+            .ok_or_error(DEFINITION_ERROR, String::from("program name prefix must be provided after --program-name"))?
+            .to_string());
+      }
+      Some("--program-version") => {
+        program_version = Some(args.pop_front()
+            .ok_or_error(DEFINITION_ERROR, String::from("program version must be provided after --program-version"))?
             .to_string());
       }
       Some("--program-summary") => {
         program_summary = Some(args.pop_front()
-            .unwrap_or_error(DEFINITION_ERROR, String::from("program summary prefix must be provided after --program-summary"))
+            .ok_or_error(DEFINITION_ERROR, String::from("program summary prefix must be provided after --program-summary"))?
             .to_string());
       }
       Some("--program-description") => {
         program_description = Some(args.pop_front()
-            .unwrap_or_error(DEFINITION_ERROR, String::from("program description prefix must be provided after --program-description"))
+            .ok_or_error(DEFINITION_ERROR, String::from("program description prefix must be provided after --program-description"))?
             .to_string());
       }
       Some("--export") => {
         export = true;
       }
+      Some("--local") => {
+        local = true;
+      }
+      Some("--shell") => {
+        let name = args.pop_front()
+            .ok_or_error(DEFINITION_ERROR, String::from("shell name must be provided after --shell"))?;
+        shell = Shell::from_name(&name)
+            .ok_or_error(DEFINITION_ERROR, format!("Unrecognized shell '{name}' provided for --shell"))?;
+        error_format.shell = shell;
+      }
+      Some("--completion") => {
+        let name = args.pop_front()
+            .ok_or_error(DEFINITION_ERROR, String::from("shell name must be provided after --completion"))?;
+        if name != "bash" {
+          return Err(error(DEFINITION_ERROR, format!("Completion is not supported for shell '{name}'")));
+        }
+        completion = Some(name);
+      }
+      Some("--help-format") => {
+        let format = args.pop_front()
+            .ok_or_error(DEFINITION_ERROR, String::from("format must be provided after --help-format"))?;
+        if format != "text" && format != "markdown" && format != "man" {
+          return Err(error(DEFINITION_ERROR, format!("Unrecognized help format '{format}' provided for --help-format")));
+        }
+        help_format = format;
+      }
+      Some("--export-spec") => {
+        export_spec = true;
+      }
+      Some("--print-spec") => {
+        print_spec = true;
+      }
+      Some("--allow-abbrev") => {
+        allow_abbrev = true;
+      }
+      Some("--group") => {
+        let title = args.pop_front()
+            .ok_or_error(DEFINITION_ERROR, String::from("group title must be provided after --group"))?;
+        groups.push((arguments.len(), title));
+      }
+      Some("--output-format") => {
+        let format = args.pop_front()
+            .ok_or_error(DEFINITION_ERROR, String::from("format must be provided after --output-format"))?;
+        if format != "shell" && format != "nul" {
+          return Err(error(DEFINITION_ERROR, format!("Unrecognized output format '{format}' provided for --output-format")));
+        }
+        error_format.plain = format == "nul";
+        output_format = format;
+      }
+      Some("--name-case") => {
+        let name_case = args.pop_front()
+            .ok_or_error(DEFINITION_ERROR, String::from("case must be provided after --name-case"))?;
+        name_style = match name_case.as_str() {
+          "upper" => NameStyle::SnakeUpper,
+          "lower" => NameStyle::SnakeLower,
+          "preserve" => NameStyle::SnakePreserve,
+          _ => {
+            return Err(error(DEFINITION_ERROR, format!("Unrecognized name case '{name_case}' provided for --name-case")));
+          }
+        };
+        name_case_given = true;
+      }
+      Some("--name-style") => {
+        let name = args.pop_front()
+            .ok_or_error(DEFINITION_ERROR, String::from("style must be provided after --name-style"))?;
+        name_style = NameStyle::from_name(&name)
+            .ok_or_error(DEFINITION_ERROR, format!("Unrecognized name style '{name}' provided for --name-style"))?;
+        name_style_given = true;
+      }
+      Some("--error-format") => {
+        let format = args.pop_front()
+            .ok_or_error(DEFINITION_ERROR, String::from("format must be provided after --error-format"))?;
+        if format != "text" && format != "json" {
+          return Err(error(DEFINITION_ERROR, format!("Unrecognized error format '{format}' provided for --error-format")));
+        }
+        error_format.json = format == "json";
+      }
+      Some("--messages-file") => {
+        let path = args.pop_front()
+            .ok_or_error(DEFINITION_ERROR, String::from("file name must be provided after --messages-file"))?;
+        messages = Messages::new(read_messages_file(&path)?);
+        error_format.messages = messages.clone();
+      }
+      Some("--quiet") => {
+        error_format.quiet = true;
+      }
+      Some("--auto-short") => {
+        auto_short = true;
+      }
+      Some("--response-files") => {
+        response_files = true;
+      }
+      Some("--validate-only") => {
+        validate_only = true;
+      }
+      Some("--declare-all") => {
+        declare_all = true;
+      }
+      Some("--skip-unset") => {
+        skip_unset = true;
+      }
+      Some("--raw-names") => {
+        raw_names = true;
+      }
+      Some("--no-pager") => {
+        no_pager = true;
+      }
+      Some("--no-tty") => {
+        no_tty = true;
+      }
+      Some("--pad-indices") => {
+        pad_indices = true;
+      }
+      Some("--env-import") => {
+        env_import = true;
+      }
+      Some("--pager-var") => {
+        let value = args.pop_front()
+            .ok_or_error(DEFINITION_ERROR, String::from("variable name must be provided after --pager-var"))?;
+        if !argument_common::is_identifier(&value) {
+          return Err(error(DEFINITION_ERROR, format!("Pager variable '{value}' is not a valid variable name")));
+        }
+        pager_var = Some(value);
+      }
+      Some("--output-array") => {
+        let value = args.pop_front()
+            .ok_or_error(DEFINITION_ERROR, String::from("array name must be provided after --output-array"))?;
+        if !argument_common::is_identifier(&value) {
+          return Err(error(DEFINITION_ERROR, format!("Array name '{value}' is not a valid variable name")));
+        }
+        output_array = Some(value);
+      }
+      Some("--rest-string") => {
+        let value = args.pop_front()
+            .ok_or_error(DEFINITION_ERROR, String::from("variable name must be provided after --rest-string"))?;
+        if !argument_common::is_identifier(&value) {
+          return Err(error(DEFINITION_ERROR, format!("Rest string name '{value}' is not a valid variable name")));
+        }
+        rest_string = Some(value);
+      }
+      Some("--emit-provided") => {
+        let value = args.pop_front()
+            .ok_or_error(DEFINITION_ERROR, String::from("variable name must be provided after --emit-provided"))?;
+        if !argument_common::is_identifier(&value) {
+          return Err(error(DEFINITION_ERROR, format!("Provided list name '{value}' is not a valid variable name")));
+        }
+        emit_provided = Some(value);
+      }
+      Some("--as-array") => {
+        as_array = true;
+      }
+      Some("--no-arrays") => {
+        arrays = false;
+      }
       Some("--prefix") => {
-        prefix = Some(args.pop_front()
-            .unwrap_or_error(DEFINITION_ERROR, String::from("argument name prefix must be provided after --prefix"))
-            .to_string());
+        let value = args.pop_front()
+            .ok_or_error(DEFINITION_ERROR, String::from("argument name prefix must be provided after --prefix"))?;
+        if !argument_common::is_identifier(&value) {
+          return Err(error(DEFINITION_ERROR, format!(
+              "Prefix '{value}' is not valid; it must start with a letter or underscore and contain only letters, digits, and underscores")));
+        }
+        prefix = Some(value);
       }
       Some("--debug") => {
         debug = true;
       }
       Some(other) => {
-        error(DEFINITION_ERROR, format!("Unrecognized option: {other}"));
+        return Err(error(DEFINITION_ERROR, format!("Unrecognized option: {other}")));
       }
     };
   }
 
-  Settings {
+  if output_array.is_some() && shell != Shell::Posix {
+    return Err(error(DEFINITION_ERROR, format!("--output-array is not supported for the {} shell", shell.get_name())));
+  }
+
+  if declare_all && skip_unset {
+    return Err(error(DEFINITION_ERROR, "--skip-unset can not be used with --declare-all"));
+  }
+
+  if output_array.is_some() && (output_format == "nul" || export) {
+    return Err(error(DEFINITION_ERROR, "--output-array can not be used with --output-format nul or --export"));
+  }
+
+  if name_case_given && name_style_given {
+    return Err(error(DEFINITION_ERROR, "--name-case and --name-style can not be used together"));
+  }
+
+  // Names with hyphens are only usable as keys of an associative array.
+  if raw_names && output_array.is_none() {
+    return Err(error(DEFINITION_ERROR, "--raw-names can only be used with --output-array"));
+  }
+
+  let name_style = if raw_names { NameStyle::Raw } else { name_style };
+  for argument in arguments.iter_mut() {
+    argument.get_common_mut().apply_name_style(name_style)?;
+  }
+
+  // Without a prefix every argument would read common variables like PATH or HOME.
+  if env_import && prefix.is_none() {
+    return Err(error(DEFINITION_ERROR, "--prefix must be provided when using --env-import"));
+  }
+
+  if env_import {
+    for argument in arguments.iter_mut() {
+      argument.get_common_mut().import_env(prefix.as_ref().unwrap());
+    }
+  }
+
+  let mut name_owners: HashMap<String, &String> = arguments.iter()
+      .map(|argument| (argument.get_name().clone(), argument.get_name()))
+      .collect();
+  for argument in arguments.iter() {
+    for name in argument.get_extra_names() {
+      match name_owners.insert(name.clone(), argument.get_name()) {
+        None => {}
+        Some(other) => {
+          return Err(error(DEFINITION_ERROR, format!("Variable {name} written for {} is already used by {other}", argument.get_name())));
+        }
+      }
+    }
+  }
+
+  if rest_string.is_some() && arguments.iter().any(|argument| Some(argument.get_name()) == rest_string.as_ref()) {
+    return Err(error(DEFINITION_ERROR, format!("--rest-string name {} is already used by an argument", rest_string.unwrap())));
+  }
+
+  if emit_provided.is_some()
+      && (arguments.iter().any(|argument| Some(argument.get_name()) == emit_provided.as_ref()) || emit_provided == rest_string) {
+    return Err(error(DEFINITION_ERROR, format!("--emit-provided name {} is already used", emit_provided.unwrap())));
+  }
+
+  // PowerShell environment variables only hold strings, and an array assigned to one is flattened.
+  // Zsh can't export arrays at all.
+  if (shell == Shell::PowerShell || shell == Shell::Zsh) && export && output_format != "nul" {
+    if emit_provided.is_some() {
+      return Err(error(DEFINITION_ERROR, format!("--emit-provided can not be used with --export for the {} shell", shell.get_name())));
+    }
+
+    let list = arguments.iter().find(|argument| argument.writes_list());
+    if list.is_some() {
+      return Err(error(DEFINITION_ERROR, format!(
+          "{} argument is written as a list, which can't be exported for the {} shell; use --join",
+          list.unwrap().get_name(),
+          shell.get_name())));
+    }
+  }
+
+  let mut skipped_short_flags = Vec::new();
+  if auto_short {
+    skipped_short_flags = add_short_flags(&mut arguments);
+  }
+
+  let mut flag_owners: HashMap<&String, usize> = HashMap::new();
+  for (i, argument) in arguments.iter().enumerate() {
+    for flag in argument.get_common().get_all_flags() {
+      match flag_owners.insert(flag, i) {
+        Some(other) if other != i => {
+          return Err(error(DEFINITION_ERROR, format!(
+              "Flag '{flag}' is used by both {} and {}",
+              arguments[other].get_name(),
+              argument.get_name())));
+        }
+        _ => {}
+      }
+    }
+  }
+
+  let mut ordinal_owners: HashMap<u16, &String> = HashMap::new();
+  for argument in arguments.iter() {
+    if argument.get_ordinal().is_some() {
+      let ordinal = argument.get_ordinal().unwrap();
+      match ordinal_owners.insert(ordinal, argument.get_name()) {
+        None => {}
+        Some(other) => {
+          return Err(error(DEFINITION_ERROR, format!("Ordinal {ordinal} is used by both {other} and {}", argument.get_name())));
+        }
+      }
+    }
+  }
+
+  for argument in arguments.iter() {
+    if argument.get_split_on().is_some() && !argument.takes_value() {
+      return Err(error(DEFINITION_ERROR, format!("{} argument does not take a value, so it can not use --split-on", argument.get_name())));
+    }
+
+    if argument.get_join().is_some() && !argument.takes_value() {
+      return Err(error(DEFINITION_ERROR, format!("{} argument does not take a value, so it can not use --join", argument.get_name())));
+    }
+
+    for reference in argument.get_conflicts_with() {
+      if !arguments.iter().any(|other| other.is_referenced_by(reference)) {
+        return Err(error(DEFINITION_ERROR, format!("{} conflicts with unknown argument '{reference}'", argument.get_name())));
+      }
+    }
+  }
+
+  if output_format == "nul" && help_function.is_some() {
+    return Err(error(DEFINITION_ERROR, "--help-function can not be used with --output-format nul"));
+  }
+
+  if auto_version && program_version.is_none() {
+    return Err(error(DEFINITION_ERROR, "--program-version must be provided when using --auto-version"));
+  }
+
+  if no_pager && pager_var.is_some() {
+    return Err(error(DEFINITION_ERROR, "--no-pager and --pager-var can not be used together"));
+  }
+
+  if export && local {
+    return Err(error(DEFINITION_ERROR, "--export and --local can not be used together"));
+  }
+
+  if export_spec && print_spec {
+    return Err(error(DEFINITION_ERROR, "--export-spec and --print-spec can not be used together"));
+  }
+
+  // Without a terminal (or with --no-tty) the width is fixed, so the help text is reproducible.
+  let columns = match (columns, termsize::get()) {
+    (Some(columns), _) => columns,
+    (None, Some(size)) if !no_tty => size.cols as usize,
+    (None, _) => 80_usize,
+  };
+
+  // Wide terminals still get help text no wider than --max-width.
+  let columns = columns.min(max_width.unwrap_or(usize::MAX));
+
+  let scope = if export {
+    Scope::Export
+  } else if local {
+    Scope::Local
+  } else {
+    Scope::Plain
+  };
+
+  let settings = Settings {
     arguments: arguments,
     prefix: prefix,
     auto_help: auto_help,
+    auto_version: auto_version,
     help_function: help_function,
-    export: export,
+    scope: scope,
     debug: debug,
     program_name: program_name,
+    program_version: program_version,
     program_summary: program_summary,
     program_description: program_description,
     remaining_args: Vec::from(args),
-    columns: columns
+    columns: columns,
+    arrays: arrays,
+    shell: shell,
+    completion: completion,
+    export_spec: export_spec,
+    print_spec: print_spec,
+    allow_abbrev: allow_abbrev,
+    help_format: help_format,
+    groups: groups,
+    as_array: as_array,
+    output_format: output_format,
+    response_files: response_files,
+    validate_only: validate_only,
+    declare_all: declare_all,
+    skip_unset: skip_unset,
+    no_pager: no_pager,
+    no_tty: no_tty,
+    pad_indices: pad_indices,
+    pager_var: pager_var,
+    output_array: output_array,
+    rest_string: rest_string,
+    emit_provided: emit_provided,
+    skipped_short_flags: skipped_short_flags,
+    messages: messages,
+    stdin_read: Cell::new(false),
+  };
+
+  for argument in settings.arguments.iter() {
+    argument.check_default(&settings)?;
+  }
+
+  return Ok(settings);
+}
+
+/// Reads `key=value` message overrides from a file. Blank lines and lines starting with `#` are
+/// skipped, and every key has to be one of the built-in message names.
+#[allow(clippy::needless_return)]
+fn read_messages_file(path: &str) -> Result<HashMap<String, String>, ArgError> {
+  let contents = std::fs::read_to_string(path)
+      .ok_or_error(DEFINITION_ERROR, format!("Unable to read messages file '{path}'"))?;
+  let mut overrides = HashMap::new();
+
+  for line in contents.lines() {
+    let line = line.trim_start();
+    if line.is_empty() || line.starts_with('#') {
+      continue;
+    }
+
+    let (key, text) = line.split_once('=')
+        .ok_or_error(DEFINITION_ERROR, format!("Line '{line}' in messages file '{path}' is not in the form key=value"))?;
+    let key = key.trim();
+    if !messages::is_message_key(key) {
+      return Err(error(DEFINITION_ERROR, format!("Unrecognized message '{key}' in messages file '{path}'")));
+    }
+
+    overrides.insert(key.to_string(), text.to_string());
   }
+
+  return Ok(overrides);
+}
+
+/// Gives each argument whose first flag is a long flag a short flag made from its first letter, as
+/// long as no other argument uses that short flag. Returns the (name, flag) pairs that were skipped.
+#[allow(clippy::needless_return, clippy::unnecessary_unwrap)]
+fn add_short_flags(arguments: &mut [Box<dyn argument::Argument>]) -> Vec<(String, String)> {
+  let mut used_flags = arguments.iter()
+      .flat_map(|argument| argument.get_common().get_all_flags().clone())
+      .collect::<Vec<String>>();
+  let mut skipped = Vec::new();
+
+  for argument in arguments.iter_mut() {
+    let letter = argument.get_common().get_all_flags().first()
+        .and_then(|flag| flag.strip_prefix("--"))
+        .and_then(|word| word.chars().next())
+        .filter(|letter| letter.is_ascii_alphanumeric());
+
+    if letter.is_some() {
+      let flag = format!("-{}", letter.unwrap());
+      if argument.get_common().get_all_flags().contains(&flag) {
+        continue;
+      } else if used_flags.contains(&flag) {
+        skipped.push((argument.get_name().clone(), flag));
+      } else {
+        used_flags.push(flag.clone());
+        argument.get_common_mut().add_flag(flag);
+      }
+    }
+  }
+
+  return skipped;
 }
 
+#[allow(clippy::unnecessary_unwrap)]
 fn debug_setup(settings: &Settings) {
   output_debug(settings, "ArgParse-sh debugging enabled with --debug flag");
   output_debug(settings, format!(
       "Arguments {} exported to child processes",
-      if settings.export { "are" } else { "are not" }));
+      if settings.scope == Scope::Export { "are" } else { "are not" }));
+
+  if settings.scope == Scope::Local {
+    output_debug(settings, "Arguments are declared local to the calling function");
+  }
 
   if settings.prefix.is_some() {
     output_debug(settings, format!("All variables will be prefixed with '{}'", settings.prefix.clone().unwrap()));
   }
 
   if settings.auto_help {
-    output_debug(settings, "Help text will be printed if '--help' is found in arguments");
+    output_debug(settings, "Help text will be printed if '--help' or '--help-all' is found in arguments");
+  }
+
+  if settings.auto_version {
+    output_debug(settings, "Version will be printed if '--version' is found in arguments");
+  }
+
+  if settings.allow_abbrev {
+    output_debug(settings, "Long flags may be abbreviated to any unique prefix");
   }
 
+  if !settings.arrays {
+    output_debug(settings, "Arrays will be written as indexed variables");
+  }
+
+  if settings.as_array {
+    output_debug(settings, "Repeated arguments will be written as arrays");
+  }
+
+  if settings.pad_indices {
+    output_debug(settings, "Indexed variables will have zero-padded indices");
+  }
+
+  if settings.validate_only {
+    output_debug(settings, "Arguments will only be validated; no values will be written");
+  }
+
+  if settings.declare_all {
+    output_debug(settings, "Arguments without a value will be set to empty");
+  }
+
+  if settings.skip_unset {
+    output_debug(settings, "Arguments without a value will be left unset");
+  }
+
+  if settings.rest_string.is_some() {
+    output_debug(settings, format!("Values that no argument takes will be written to {} as a command line", settings.rest_string.as_ref().unwrap()));
+  }
+
+  if settings.emit_provided.is_some() {
+    output_debug(settings, format!("Names of arguments given on the command line will be written to {}", settings.emit_provided.as_ref().unwrap()));
+  }
+
+  if settings.output_array.is_some() {
+    output_debug(settings, format!("Values will be written to the associative array {}", settings.output_array.as_ref().unwrap()));
+  }
+
+  if settings.output_format == "nul" {
+    output_debug(settings, "Output will be written as NUL-delimited name and value records");
+  } else {
+    output_debug(settings, format!("Output will be written for the {} shell", settings.shell.get_name()));
+  }
   output_debug(settings, format!("Help text will be formatted with {} columns", settings.columns));
+  if settings.no_pager {
+    output_debug(settings, "Help text will be printed without a pager");
+  }
+  if settings.no_tty {
+    output_debug(settings, "Help text will be generated as if there is no terminal");
+  }
+  if settings.pager_var.is_some() {
+    output_debug(settings, format!("Help text pager will be read from {} before PAGER", settings.pager_var.clone().unwrap()));
+  }
 
   output_debug(settings, "");
 
   for arg in settings.arguments.iter() {
     output_debug(settings, format!("Definition - {}", arg.get_debug_info()));
   }
-}
 
-fn parse_argument_values(settings: &Settings) -> HashMap<String, Vec<String>> {
-  let mut args = VecDeque::from(settings.remaining_args.clone());
+  for (name, flag) in settings.skipped_short_flags.iter() {
+    output_debug(settings, format!("Short flag {flag} was not added to {name} because it is already in use"));
+  }
+
+  // A literal default for a secret ends up in the script itself, which defeats the secrecy.
+  for arg in settings.arguments.iter() {
+    if arg.is_secret() && arg.get_default().as_ref().is_some_and(|default| !default.is_empty()) {
+      output_debug(settings, format!(
+          "Warning - secret argument {} has a default, which is visible in plain text; consider --env instead",
+          arg.get_name()));
+    }
+  }
+
+  // Gaps are allowed, but are often the result of a removed or mistyped ordinal.
+  let mut ordinals = settings.arguments.iter()
+      .filter_map(|arg| *arg.get_ordinal())
+      .collect::<Vec<u16>>();
+  ordinals.sort();
+  for pair in ordinals.windows(2) {
+    if pair[1] > pair[0] + 1 {
+      output_debug(settings, format!("Warning - no arguments use the ordinals between {} and {}", pair[0], pair[1]));
+    }
+  }
+}
 
+#[allow(clippy::needless_return, clippy::unnecessary_unwrap)]
+fn parse_argument_values(settings: &Settings) -> Result<HashMap<String, Vec<String>>, ArgError> {
   output_debug(settings, "");
   output_debug(settings, "Parsing argument values");
   output_debug(settings, "");
 
-  let mut result = HashMap::new();
+  let mut args = if settings.response_files {
+    VecDeque::from(expand_response_files(settings, &settings.remaining_args)?)
+  } else {
+    VecDeque::from(settings.remaining_args.clone())
+  };
+
+  let mut result: HashMap<String, Vec<String>> = HashMap::new();
   let mut ordinal = 0_u16;
+  let mut positional_only = false;
 
   while !args.is_empty() {
     let arg = args.pop_front().unwrap();
-    let (name, value, new_ordinal) = parse_argument_value(&settings, ordinal, &arg, &mut args, &result);
+
+    // Once the rest string has started, everything after it is kept as it was given.
+    let rest = settings.rest_string.as_ref().and_then(|name| result.get_mut(name));
+    if rest.is_some() {
+      rest.unwrap().push(arg);
+      continue;
+    }
+
+    if arg == "--" && !positional_only {
+      output_debug(settings, "Found '--'; all remaining arguments are positional");
+      positional_only = true;
+      continue;
+    }
+
+    let (name, values, new_ordinal) = if positional_only {
+      parse_positional_value(settings, ordinal, &arg, &result)?
+    } else {
+      parse_argument_value(settings, ordinal, &arg, &mut args, &result)?
+    };
     ordinal = new_ordinal;
 
-    let mut all_values = result.remove(&name).unwrap_or(Vec::new());
-    all_values.push(value);
+    let mut all_values = result.remove(&name).unwrap_or_default();
+    all_values.extend(values);
     result.insert(name, all_values);
   }
 
-  return result;
+  return Ok(result);
+}
+
+/// Replaces each `@file` argument with the whitespace-separated contents of that file. An argument
+/// starting with `@@` is kept, minus the first `@`.
+#[allow(clippy::needless_return)]
+fn expand_response_files(settings: &Settings, args: &[String]) -> Result<Vec<String>, ArgError> {
+  let mut result = Vec::new();
+
+  for arg in args {
+    if arg.starts_with("@@") {
+      result.push(arg[1..].to_string());
+    } else if let Some(path) = arg.strip_prefix('@') {
+      let contents = std::fs::read_to_string(path)
+          .ok_or_error(USER_ERROR, settings.messages.get("response_file", &[("path", &path)]))?;
+      output_debug(settings, format!("Read arguments from response file '{path}'"));
+      result.extend(contents.split_whitespace().map(String::from));
+    } else {
+      result.push(arg.clone());
+    }
+  }
+
+  return Ok(result);
+}
+
+/// Splits a bundle of short flags like `-abc` into `-a -b -c`. Once a letter belongs to an argument
+/// that takes a value, the rest of the bundle becomes that value, so `-vofile` is `-v -o file`.
+/// Returns None unless every letter up to that point is a known short flag.
+#[allow(clippy::needless_return)]
+fn expand_short_flags(settings: &Settings, token: &str) -> Option<Vec<String>> {
+  if token.starts_with("--") || !token.starts_with('-') || token.chars().count() < 3 {
+    return None;
+  }
+
+  let letters = token.chars().skip(1).collect::<Vec<char>>();
+  let mut result = Vec::new();
+
+  for (i, letter) in letters.iter().enumerate() {
+    let flag = format!("-{letter}");
+    let argument = settings.arguments.iter()
+        .find(|argument| argument.get_common().get_all_flags().contains(&flag))?;
+    result.push(flag);
+
+    if argument.takes_value() {
+      let value = letters[i + 1..].iter().collect::<String>();
+      if !value.is_empty() {
+        result.push(value);
+      }
+      break;
+    }
+  }
+
+  return Some(result);
 }
 
+#[allow(clippy::needless_return)]
 fn parse_argument_value(
     settings: &Settings,
     ordinal: u16,
     first: &String,
     rest: &mut VecDeque<String>,
     known_values: &HashMap<String, Vec<String>>,
-) -> (String, String, u16) {
+) -> Result<(String, Vec<String>, u16), ArgError> {
   // First pass handles flag cases (`--arg value` and `--arg=value`).
   for argument in settings.arguments.iter() {
-    match argument.consume(Some(first.clone()), rest) {
+    match argument.consume(settings, Some(first.clone()), rest)? {
       None => {}
-      Some(value) => {
+      Some(values) => {
         let name = argument.get_name().to_string();
-        output_debug(settings, format!("Parsed argument {name} = '{value}' [flag: '{first}']"));
-        return (name, value, ordinal);
+        output_debug(settings, format!("Parsed argument {name} = {} [flag: '{first}']", debug_values(argument.is_secret(), &values)));
+        return Ok((name, values, ordinal));
+      }
+    }
+  }
+
+  // Bundled short flags (`-abc`) are split apart when every letter is a known short flag.
+  match expand_short_flags(settings, first) {
+    None => {}
+    Some(expanded) => {
+      output_debug(settings, format!("Expanded bundled flags '{first}' to {}", expanded.join(" ")));
+      for token in expanded.into_iter().rev() {
+        rest.push_front(token);
+      }
+      let next = rest.pop_front().unwrap();
+      return parse_argument_value(settings, ordinal, &next, rest, known_values);
+    }
+  }
+
+  // Abbreviated long flags are only considered once no flag matches exactly.
+  if settings.allow_abbrev {
+    match expand_abbreviation(settings, first)? {
+      None => {}
+      Some(expanded) => {
+        for argument in settings.arguments.iter() {
+          match argument.consume(settings, Some(expanded.clone()), rest)? {
+            None => {}
+            Some(values) => {
+              let name = argument.get_name().to_string();
+              output_debug(settings, format!("Parsed argument {name} = {} [flag: '{first}' -> '{expanded}']", debug_values(argument.is_secret(), &values)));
+              return Ok((name, values, ordinal));
+            }
+          }
+        }
       }
     }
   }
 
-  // Second pass handles ordinals.
+  return parse_positional_value(settings, ordinal, first, known_values);
+}
+
+/// Gives a value that is not a flag to the next unfilled ordinal argument, or failing that to a
+/// catch-all argument. Every ordinal is filled before any catch-all gets a value, however the
+/// arguments were defined. A value that nothing takes starts the `--rest-string`, if there is one.
+#[allow(clippy::needless_return, clippy::unnecessary_unwrap)]
+fn parse_positional_value(
+    settings: &Settings,
+    ordinal: u16,
+    first: &str,
+    known_values: &HashMap<String, Vec<String>>,
+) -> Result<(String, Vec<String>, u16), ArgError> {
+  // Ordinals are filled first.
   let next_ordinal_argument = settings.arguments.iter()
       .filter(|a| a.get_ordinal().is_some() && !known_values.contains_key(a.get_name()))
-      .min_by_key(|a| a.get_ordinal().clone().unwrap());
+      .min_by_key(|a| a.get_ordinal().unwrap());
 
   match next_ordinal_argument {
     None => {}
     Some(argument) => {
       let name = argument.get_name().to_string();
-      let value = argument.consume(None, &mut VecDeque::from(vec![first.clone()])).unwrap();
-      output_debug(settings, format!("Parsed argument {name} = '{value}' [ordinal: {}]", argument.get_ordinal().clone().unwrap()));
-      return (name, value, ordinal + 1);
+      let values = argument.consume(settings, None, &mut VecDeque::from(vec![first.to_string()]))?.unwrap();
+      output_debug(settings, format!("Parsed argument {name} = {} [ordinal: {}]", debug_values(argument.is_secret(), &values), argument.get_ordinal().unwrap()));
+      return Ok((name, values, ordinal + 1));
     }
   }
 
-  // Third pass handles catch-all cases.
+  // Then catch-all arguments.
   for argument in settings.arguments.iter() {
     if argument.is_catch_all() && (argument.is_repeated() || !known_values.contains_key(argument.get_name())) {
       let name = argument.get_name().to_string();
-      let value = argument.consume(None, &mut VecDeque::from(vec![first.clone()])).unwrap();
-      output_debug(settings, format!("Parsed argument {name} = '{value}' [catch-all]"));
-      return (name, value, ordinal + 1);
+      let values = argument.consume(settings, None, &mut VecDeque::from(vec![first.to_string()]))?.unwrap();
+      output_debug(settings, format!("Parsed argument {name} = {} [catch-all]", debug_values(argument.is_secret(), &values)));
+      return Ok((name, values, ordinal + 1));
     }
   }
 
-  error(USER_ERROR, format!("Extra argument \"{first}\" passed and no catch-all argument found"));
-  panic!("");
-}
+  if settings.rest_string.is_some() {
+    output_debug(settings, format!("Found '{first}'; it and all remaining arguments go to {}", settings.rest_string.as_ref().unwrap()));
+    return Ok((settings.rest_string.clone().unwrap(), vec![first.to_string()], ordinal));
+  }
 
-fn validate_argument_values(settings: &Settings, arg_values: &HashMap<String, Vec<String>>) {
-  output_debug(settings, "");
+  return Err(match closest_flag(settings, first) {
+    None => error(USER_ERROR, settings.messages.get("extra_argument", &[("value", &first)])),
+    Some(flag) => error(USER_ERROR, settings.messages.get("did_you_mean", &[("value", &first), ("flag", &flag)])),
+  });
+}
 
-  for argument in settings.arguments.iter() {
-    let values = arg_values.get(argument.get_name());
-    if values.is_some() {
-      let values = values.unwrap();
-      if !argument.is_repeated() && values.len() > 1 {
-        error(USER_ERROR, format!("Multiple values found for argument {}", argument.get_name()));
-      }
-    } else if argument.is_required() {
-      error(USER_ERROR, format!("Value for argument {} is missing", argument.get_name()));
-    }
+/// Finds the flag closest to a mistyped one, for suggesting a fix. Only flags within an edit
+/// distance of 2 are suggested, and secret arguments are never suggested. Numbers such as `-5` are
+/// values rather than typos, and short flags like `-n` are too close to everything to be useful.
+#[allow(clippy::needless_return)]
+fn closest_flag(settings: &Settings, token: &str) -> Option<String> {
+  if !token.starts_with('-') || token.parse::<f64>().is_ok() {
+    return None;
   }
+
+  let typed = token.split('=').next().unwrap();
+  return settings.arguments.iter()
+      .filter(|argument| !argument.is_secret())
+      .flat_map(|argument| argument.get_common().get_all_flags().iter())
+      .filter(|flag| flag.chars().count() >= 4)
+      .map(|flag| (edit_distance(typed, flag), flag))
+      .filter(|(distance, _)| *distance <= 2)
+      .min_by_key(|(distance, _)| *distance)
+      .map(|(_, flag)| flag.clone());
 }
 
-fn output_argument_settings(settings: &Settings, arg_values: &HashMap<String, Vec<String>>) {
-  for argument in settings.arguments.iter() {
-    let values = arg_values.get(argument.get_name());
-    if values.is_some() {
-      let values = values.unwrap();
+/// Computes the Levenshtein distance between two strings, counting characters rather than bytes.
+#[allow(clippy::needless_return)]
+fn edit_distance(a: &str, b: &str) -> usize {
+  let b = b.chars().collect::<Vec<char>>();
+  let mut previous = (0..=b.len()).collect::<Vec<usize>>();
 
-      if argument.is_repeated() {
-        output_argument(settings, argument.get_name(), values.len());
-        for i in 0..values.len() {
-          output_argument(settings, &format!("{}_{}", argument.get_name(), i), values.get(i).unwrap());
-        }
-      } else {
-        output_argument(settings, argument.get_name(), values.get(0).unwrap());
-      }
-    } else if argument.get_default().is_some() {
-      output_argument(settings, argument.get_name(), argument.get_default().clone().unwrap());
+  for (i, a_char) in a.chars().enumerate() {
+    let mut current = vec![i + 1];
+    for (j, b_char) in b.iter().enumerate() {
+      let substitution = previous[j] + if a_char == *b_char { 0 } else { 1 };
+      current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
     }
+    previous = current;
   }
 
-  output_debug(settings, "");
+  return previous[b.len()];
+}
+
+/// Expands a long flag that is a unique prefix of exactly one defined long flag. Any `=value` suffix is
+/// carried over to the expanded flag. Returns None if the token is not an abbreviation of any flag.
+#[allow(clippy::needless_return)]
+fn expand_abbreviation(settings: &Settings, token: &str) -> Result<Option<String>, ArgError> {
+  if !token.starts_with("--") {
+    return Ok(None);
+  }
+
+  let (prefix, suffix) = match token.split_once('=') {
+    None => (token, String::from("")),
+    Some((prefix, value)) => (prefix, format!("={value}")),
+  };
+
+  let candidates = settings.arguments.iter()
+      .flat_map(|argument| argument.get_common().get_all_flags().iter())
+      .filter(|flag| flag.starts_with("--") && flag.starts_with(prefix))
+      .cloned()
+      .collect::<Vec<String>>();
+
+  if candidates.len() > 1 {
+    return Err(error(USER_ERROR, settings.messages.get("ambiguous_flag", &[("flag", &prefix), ("options", &candidates.join(", "))])));
+  }
+
+  return Ok(candidates.first().map(|flag| format!("{flag}{suffix}")));
+}
+
+/// Where the value of an argument comes from. A value on the command line wins over one from the
+/// environment, which wins over the default.
+enum ValueSource<'a> {
+  CommandLine(&'a Vec<String>),
+  Environment(Vec<String>),
+  Default(&'a String),
+  Missing,
+}
+
+/// Works out the source of each argument's value, in the same order as `settings.arguments`.
+/// Values taken from the environment are parsed and checked just like values on the command line.
+#[allow(clippy::needless_return, clippy::unnecessary_unwrap)]
+fn resolve_value_sources<'a>(settings: &'a Settings, arg_values: &'a HashMap<String, Vec<String>>) -> Result<Vec<ValueSource<'a>>, ArgError> {
+  let mut sources = Vec::new();
+
+  for argument in settings.arguments.iter() {
+    let values = arg_values.get(argument.get_name());
+    let env_value = argument.get_env().as_ref().and_then(|env| std::env::var(env).ok());
+
+    if values.is_some() {
+      sources.push(ValueSource::CommandLine(values.unwrap()));
+    } else if env_value.is_some() {
+      output_debug(settings, format!(
+          "Value for {} taken from environment variable {}",
+          argument.get_name(),
+          argument.get_env().as_ref().unwrap()));
+      sources.push(ValueSource::Environment(parse_env_value(settings, argument.as_ref(), env_value.unwrap())?));
+    } else if argument.get_default().is_some() {
+      argument.check_used_default(settings, argument.get_default().as_ref().unwrap())?;
+      sources.push(ValueSource::Default(argument.get_default().as_ref().unwrap()));
+    } else {
+      sources.push(ValueSource::Missing);
+    }
+  }
+
+  return Ok(sources);
+}
+
+/// Runs a value from the environment through the argument's own parsing, as if it had been given
+/// on the command line as `--flag=value` (or as a positional value for arguments without flags).
+#[allow(clippy::needless_return)]
+fn parse_env_value(settings: &Settings, argument: &dyn argument::Argument, value: String) -> Result<Vec<String>, ArgError> {
+  let parsed = match argument.get_common().get_all_flags().first() {
+    Some(flag) => argument.consume(settings, Some(format!("{flag}={value}")), &mut VecDeque::new())?,
+    None => argument.consume(settings, None, &mut VecDeque::from([value]))?,
+  };
+
+  return Ok(parsed.unwrap());
+}
+
+/// Checks the values against the argument definitions. Every problem found is reported together,
+/// rather than stopping at the first one.
+#[allow(clippy::needless_return, clippy::unnecessary_unwrap)]
+fn validate_argument_values(settings: &Settings, arg_values: &HashMap<String, Vec<String>>, sources: &[ValueSource]) -> Result<(), ArgError> {
+  output_debug(settings, "");
+  let mut errors = Vec::new();
+
+  for (argument, source) in settings.arguments.iter().zip(sources) {
+    let values = arg_values.get(argument.get_name());
+    match source {
+      ValueSource::CommandLine(values) if !argument.is_repeated() && values.len() > 1 => {
+        errors.push(settings.messages.get("multiple_values", &[("name", argument.get_name())]));
+      }
+      ValueSource::Missing if argument.is_required() && argument.is_catch_all() => {
+        errors.push(settings.messages.get("missing_catch_all", &[("name", argument.get_name())]));
+        continue;
+      }
+      ValueSource::Missing if argument.is_required() => {
+        errors.push(settings.messages.get("missing_value", &[("name", argument.get_name())]));
+        continue;
+      }
+      _ => {}
+    }
+
+    // A value from the environment or a default stands in for missing values, so the minimum only
+    // applies when there is neither.
+    let count = values.map(|values| values.len()).unwrap_or(0);
+    let stands_in = matches!(source, ValueSource::Environment(_) | ValueSource::Default(_));
+    if argument.get_min_count().is_some() && count < argument.get_min_count().unwrap() && !stands_in {
+      errors.push(settings.messages.get("too_few_values", &[
+          ("name", argument.get_name()),
+          ("min", &argument.get_min_count().unwrap()),
+          ("count", &count),
+      ]));
+    }
+
+    if argument.get_max_count().is_some() && count > argument.get_max_count().unwrap() {
+      errors.push(settings.messages.get("too_many_values", &[
+          ("name", argument.get_name()),
+          ("max", &argument.get_max_count().unwrap()),
+          ("count", &count),
+      ]));
+    }
+  }
+
+  for group in group_names(settings, |argument| argument.get_mutex_groups()) {
+    let provided = settings.arguments.iter()
+        .filter(|argument| argument.get_mutex_groups().contains(&group) && arg_values.contains_key(argument.get_name()))
+        .map(|argument| argument.get_display_flag())
+        .collect::<Vec<String>>();
+
+    if provided.len() > 1 {
+      errors.push(settings.messages.get("mutex", &[("flags", &provided.join(", "))]));
+    }
+  }
+
+  // Like mutex groups, only values the user actually provided can conflict.
+  for (argument, source) in settings.arguments.iter().zip(sources) {
+    if !matches!(source, ValueSource::CommandLine(_) | ValueSource::Environment(_)) {
+      continue;
+    }
+
+    for reference in argument.get_conflicts_with() {
+      let conflict = settings.arguments.iter()
+          .zip(sources)
+          .find(|(other, other_source)| other.is_referenced_by(reference)
+              && matches!(other_source, ValueSource::CommandLine(_) | ValueSource::Environment(_)));
+
+      if conflict.is_some() {
+        errors.push(settings.messages.get("conflict", &[
+            ("flag", &argument.get_display_flag()),
+            ("other", &conflict.unwrap().0.get_display_flag()),
+        ]));
+      }
+    }
+  }
+
+  // Values from the environment count towards a one-of group, but defaults don't.
+  for group in group_names(settings, |argument| argument.get_one_of_groups()) {
+    let members = settings.arguments.iter()
+        .zip(sources)
+        .filter(|(argument, _)| argument.get_one_of_groups().contains(&group))
+        .collect::<Vec<_>>();
+
+    let any_provided = members.iter()
+        .any(|(_, source)| matches!(source, ValueSource::CommandLine(_) | ValueSource::Environment(_)));
+
+    if !any_provided {
+      let flags = members.iter().map(|(argument, _)| argument.get_display_flag()).collect::<Vec<String>>();
+      errors.push(settings.messages.get("one_of", &[("flags", &flags.join(", "))]));
+    }
+  }
+
+  if !errors.is_empty() {
+    return Err(ArgError::User(errors.join("\n")));
+  }
+  return Ok(());
+}
+
+/// Gets the names of all groups of one kind (such as mutex groups), in the order they were first
+/// defined.
+#[allow(clippy::needless_return)]
+fn group_names(settings: &Settings, get_groups: fn(&dyn argument::Argument) -> &Vec<String>) -> Vec<String> {
+  let mut groups: Vec<String> = Vec::new();
+  for argument in settings.arguments.iter() {
+    for group in get_groups(argument.as_ref()) {
+      if !groups.contains(group) {
+        groups.push(group.clone());
+      }
+    }
+  }
+  return groups;
+}
+
+#[allow(clippy::unnecessary_unwrap)]
+fn output_argument_settings(settings: &Settings, arg_values: &HashMap<String, Vec<String>>, sources: &[ValueSource]) {
+  if settings.output_array.is_some() {
+    outln!("{} -A {}", if settings.scope == Scope::Local { "local" } else { "declare -g" }, settings.output_array.as_ref().unwrap());
+  }
+
+  for (argument, source) in settings.arguments.iter().zip(sources) {
+    match source {
+      ValueSource::CommandLine(values) => argument.output_values(settings, values),
+      ValueSource::Environment(values) => argument.output_values(settings, values),
+      ValueSource::Default(default) => argument.output_default(settings, default),
+      ValueSource::Missing if settings.declare_all => argument.output_missing(settings),
+      // With `--skip-unset` nothing at all is written, not even a debug line or a count.
+      ValueSource::Missing if settings.skip_unset => {}
+      // Repeated arguments always get a count, so loops over their values work without a guard.
+      ValueSource::Missing if argument.is_repeated() => argument.output_values(settings, &[]),
+      ValueSource::Missing => {}
+    }
+  }
+
+  // Each value is quoted on its own, so that evaluating the string gives back the same values.
+  if settings.rest_string.is_some() {
+    let name = settings.rest_string.as_ref().unwrap();
+    let rest = arg_values.get(name).map(|values| values.as_slice()).unwrap_or(&[]);
+    let command_line = rest.iter().map(|value| settings.shell.quote(value)).collect::<Vec<String>>().join(" ");
+    output_argument(settings, name, command_line, false);
+  }
+
+  // Only values from the command line count; defaults and environment variables are left out.
+  if settings.emit_provided.is_some() {
+    let name = settings.emit_provided.as_ref().unwrap();
+    let provided = settings.arguments
+        .iter()
+        .map(|argument| argument.get_name())
+        .filter(|argument_name| arg_values.contains_key(*argument_name))
+        .cloned()
+        .collect::<Vec<String>>();
+    if settings.arrays || settings.shell.has_native_lists() {
+      output_array(settings, name, &provided, false);
+    } else {
+      output_indexed(settings, name, &provided, false);
+    }
+  }
+
+  output_debug(settings, "");
   output_debug(settings, "ArgParse-sh completed successfully");
 }
 
-fn cleanup_help_text(text: &Option<String>, options: &Options) -> String {
+/// Joins single line breaks into spaces and collapses runs of blank lines into paragraph breaks. A
+/// line holding only `\\` is kept as a line break within the paragraph.
+#[allow(clippy::needless_return)]
+fn join_help_lines(text: &str) -> String {
   let regex = Regex::new(r"(?m)(?P<text>.+?)\s*?(?P<lines>\n+|$)").unwrap();
   let mut result = String::from("");
 
-  for chunk in regex.captures_iter(text.clone().unwrap().as_str()) {
-    result.push_str(&chunk["text"]);
+  for chunk in regex.captures_iter(text) {
     let lines = &chunk["lines"];
+    if chunk["text"].trim() == "\\\\" {
+      result.truncate(result.trim_end_matches(' ').len());
+      result.push_str(if lines.len() > 1 { "\n\n" } else { "\n" });
+      continue;
+    }
+
+    result.push_str(&chunk["text"]);
     if lines.len() == 1 {
-      result.push_str(" ");
+      result.push(' ');
     } else {
       result.push_str("\n\n");
     }
   }
 
-  return fill(result.trim_end(), options)
-      .to_string()
-      .replace("\"", "\\\"")
-      .replace("$", "\\$");
+  return result.trim_end().to_string();
 }
 
-fn print_help_text(settings: &Settings) {
+/// Wraps help text to the given options. The blank line between paragraphs is kept free of the
+/// indentation, which would otherwise be left as trailing spaces.
+#[allow(clippy::needless_return)]
+fn cleanup_help_text(settings: &Settings, text: &Option<String>, options: &Options) -> String {
+  let filled = fill(&join_help_lines(text.clone().unwrap().as_str()), options)
+      .lines()
+      .map(|line| line.trim_end())
+      .collect::<Vec<&str>>()
+      .join("\n");
+  return settings.shell.escape_double_quoted(filled);
+}
+
+/// Splits the arguments into help sections: ungrouped arguments first, then each `--group` in the
+/// order it was first opened. A group title that is used again adds to the existing section.
+#[allow(clippy::needless_return)]
+fn help_sections(settings: &Settings) -> Vec<(Option<String>, Vec<&dyn argument::Argument>)> {
+  let mut sections: Vec<(Option<String>, Vec<&dyn argument::Argument>)> = Vec::new();
+
+  let ungrouped_end = settings.groups.first().map(|(start, _)| *start).unwrap_or(settings.arguments.len());
+  if ungrouped_end > 0 {
+    sections.push((None, settings.arguments[..ungrouped_end].iter().map(|arg| arg.as_ref()).collect()));
+  }
+
+  for (i, (start, title)) in settings.groups.iter().enumerate() {
+    let end = settings.groups.get(i + 1).map(|(next, _)| *next).unwrap_or(settings.arguments.len());
+    let arguments = settings.arguments[*start..end].iter().map(|arg| arg.as_ref());
+
+    match sections.iter_mut().find(|(existing, _)| existing.as_ref() == Some(title)) {
+      Some((_, section)) => section.extend(arguments),
+      None => sections.push((Some(title.clone()), arguments.collect())),
+    }
+  }
+
+  sections.retain(|(_, section)| !section.is_empty());
+  return sections;
+}
+
+/// Gets the tags shown after an argument's flags in help text: `[required]` for required arguments
+/// and `(hidden)` for secret arguments, which are only shown by `--help-all`.
+#[allow(clippy::needless_return)]
+fn help_flag_tags(arg: &dyn argument::Argument) -> String {
+  let mut tags = String::from("");
+  if arg.is_required() {
+    tags.push_str(" [required]");
+  }
+  if arg.is_secret() {
+    tags.push_str(" (hidden)");
+  }
+  return tags;
+}
+
+/// Describes the constraints on an argument that are not specific to its type: how many values it
+/// takes, which other arguments can not be combined with it because of shared mutex groups or
+/// conflicts, which one-of groups it belongs to, how its values can be split, and the environment
+/// variable it can be read from.
+#[allow(clippy::needless_return)]
+fn constraint_help_lines(settings: &Settings, arg: &dyn argument::Argument) -> Vec<String> {
+  let mut lines = Vec::new();
+
+  match (arg.get_min_count(), arg.get_max_count()) {
+    (Some(min), Some(max)) => lines.push(format!("Between {min} and {max} values must be provided.")),
+    (Some(min), None) => lines.push(format!("At least {min} values must be provided.")),
+    (None, Some(max)) => lines.push(format!("At most {max} values can be provided.")),
+    (None, None) => {}
+  }
+
+  for group in arg.get_mutex_groups() {
+    let others = settings.arguments.iter()
+        .filter(|other| other.get_name() != arg.get_name() && other.get_mutex_groups().contains(group))
+        .map(|other| other.get_display_flag())
+        .collect::<Vec<String>>();
+
+    if !others.is_empty() {
+      lines.push(format!("Part of the '{group}' group; can not be combined with {}.", others.join(", ")));
+    }
+  }
+
+  let conflicts = settings.arguments.iter()
+      .filter(|other| arg.get_conflicts_with().iter().any(|reference| other.is_referenced_by(reference)))
+      .map(|other| other.get_display_flag())
+      .collect::<Vec<String>>();
+  if !conflicts.is_empty() {
+    lines.push(format!("Can not be combined with {}.", conflicts.join(", ")));
+  }
+
+  for group in arg.get_one_of_groups() {
+    let members = settings.arguments.iter()
+        .filter(|other| other.get_one_of_groups().contains(group))
+        .map(|other| other.get_display_flag())
+        .collect::<Vec<String>>();
+
+    lines.push(format!("Part of the '{group}' group; at least one of {} must be provided.", members.join(", ")));
+  }
+
+  if arg.get_split_on().is_some() {
+    lines.push(format!("Several values can be given at once, separated by '{}'.", arg.get_split_on().as_ref().unwrap()));
+  }
+
+  if arg.get_env().is_some() {
+    lines.push(format!(
+        "When this option is not provided it is read from the {} environment variable, if set.",
+        arg.get_env().as_ref().unwrap()));
+  }
+
+  return lines;
+}
+
+/// Writes the help text in the configured format. Secret arguments are left out unless
+/// `show_secret` is set, in which case they are included and marked as hidden.
+#[allow(clippy::unnecessary_unwrap)]
+fn print_help_text(settings: &Settings, show_secret: bool) {
+  if settings.help_format == "markdown" {
+    print_help_markdown(settings, show_secret);
+    return;
+  } else if settings.help_format == "man" {
+    print_help_man(settings, show_secret);
+    return;
+  }
+
   let shallow_options = Options::new(settings.columns)
       .initial_indent("       ")
       .subsequent_indent("       ");
@@ -299,131 +1411,865 @@ fn print_help_text(settings: &Settings) {
       .initial_indent("           •   ")
       .subsequent_indent("               ");
 
-  println!("(");
+  let bold = settings.shell.variable_in_string("bold");
+  let unbold = settings.shell.variable_in_string("unbold");
+
+  match settings.shell {
+    Shell::Posix | Shell::Zsh if settings.no_pager => {
+      outln!("(");
+      outln!("bold=\"\"");
+      outln!("unbold=\"\"");
+      outln!("HELP_TEXT=\"");
+    }
+    Shell::Posix | Shell::Zsh => {
+      outln!("(");
+
+      if settings.no_tty {
+        outln!("bold=\"\"");
+        outln!("unbold=\"\"");
+      } else {
+        outln!("if [ -t 1 ] && [ -z \"${{NO_COLOR:-}}\" ]; then");
+        outln!("  bold=\"$(tput bold)\"");
+        outln!("  unbold=\"$(tput sgr0)\"");
+        outln!("else");
+        outln!("  bold=\"\"");
+        outln!("  unbold=\"\"");
+        outln!("fi");
+      }
+
+      match &settings.pager_var {
+        None => outln!("HELP_PAGER=\"${{PAGER:-\"less -R\"}}\""),
+        Some(var) => outln!("HELP_PAGER=\"${{{var}:-${{PAGER:-\"less -R\"}}}}\""),
+      }
+      outln!("HELP_TEXT=\"");
+    }
+    Shell::Fish if settings.no_pager => {
+      outln!("begin");
+      outln!("set -l bold \"\"");
+      outln!("set -l unbold \"\"");
+      outln!("set -l HELP_TEXT \"");
+    }
+    Shell::Fish => {
+      outln!("begin");
 
-  println!("if [ -t 1 ]; then");
-  println!("  bold=\"$(tput bold)\"");
-  println!("  unbold=\"$(tput sgr0)\"");
-  println!("else");
-  println!("  bold=\"\"");
-  println!("  unbold=\"\"");
-  println!("fi");
+      outln!("set -l bold \"\"");
+      outln!("set -l unbold \"\"");
+      if !settings.no_tty {
+        outln!("if isatty stdout; and test -z \"$NO_COLOR\"");
+        outln!("  set bold (tput bold)");
+        outln!("  set unbold (tput sgr0)");
+        outln!("end");
+      }
+
+      match &settings.pager_var {
+        None => outln!("set -l HELP_PAGER \"$PAGER\""),
+        Some(var) => {
+          outln!("set -l HELP_PAGER \"${var}\"");
+          outln!("if test -z \"$HELP_PAGER\"");
+          outln!("  set HELP_PAGER \"$PAGER\"");
+          outln!("end");
+        }
+      }
+      outln!("if test -z \"$HELP_PAGER\"");
+      outln!("  set HELP_PAGER \"less -R\"");
+      outln!("end");
+      outln!("set -l HELP_TEXT \"");
+    }
+    Shell::PowerShell => {
+      outln!("& {{");
+      outln!("$bold = \"\"");
+      outln!("$unbold = \"\"");
+      outln!("$HELP_TEXT = @\"");
+    }
+  }
 
-  println!("HELP_PAGER=\"${{PAGER:-\"less -R\"}}\"");
-  println!("HELP_TEXT=\"");
+  let program_name = match (&settings.program_name, &settings.program_version) {
+    (Some(name), Some(version)) => Some(format!("{name} {version}")),
+    (name, _) => name.clone(),
+  };
 
-  if settings.program_name.is_some() && settings.program_summary.is_some() {
-    println!("${{bold}}NAME${{unbold}}");
-    println!("{}", cleanup_help_text(
-        &Some(format!("{} - {}", settings.program_name.clone().unwrap(), settings.program_summary.clone().unwrap())),
+  if program_name.is_some() && settings.program_summary.is_some() {
+    outln!("{bold}NAME{unbold}");
+    outln!("{}", cleanup_help_text(
+        settings,
+        &Some(format!("{} - {}", program_name.unwrap(), settings.program_summary.clone().unwrap())),
         &shallow_options));
-    println!("");
-  } else if settings.program_name.is_some() {
-    println!("${{bold}}NAME${{unbold}}");
-    println!("{}", cleanup_help_text(&settings.program_name, &shallow_options));
-    println!("");
+    outln!();
+  } else if program_name.is_some() {
+    outln!("{bold}NAME{unbold}");
+    outln!("{}", cleanup_help_text(settings, &program_name, &shallow_options));
+    outln!();
   } else if settings.program_summary.is_some() {
-    println!("${{bold}}SUMMARY${{unbold}}");
-    println!("{}", cleanup_help_text(&settings.program_summary, &shallow_options));
-    println!("");
+    outln!("{bold}SUMMARY{unbold}");
+    outln!("{}", cleanup_help_text(settings, &settings.program_summary, &shallow_options));
+    outln!();
   }
 
   if settings.program_description.is_some() {
-    println!("${{bold}}DESCRIPTION${{unbold}}");
-    println!("{}", cleanup_help_text(&settings.program_description, &shallow_options));
-    println!("");
+    outln!("{bold}DESCRIPTION{unbold}");
+    outln!("{}", cleanup_help_text(settings, &settings.program_description, &shallow_options));
+    outln!();
   }
 
-  if !settings.arguments.is_empty() {
-    println!("${{bold}}OPTIONS${{unbold}}");
+  for (title, section) in help_sections(settings) {
+    outln!("{bold}{}{unbold}", settings.shell.escape_double_quoted(title.unwrap_or(String::from("OPTIONS"))));
 
-    for arg in settings.arguments.iter() {
-      if !arg.is_secret() {
+    for arg in section {
+      if !arg.is_secret() || show_secret {
         let mut line_so_far = String::from("");
         for (i, flag) in arg.get_help_flags().iter().enumerate() {
           if i == 0 {
             line_so_far = format!("       {flag}");
           } else if UnicodeWidthStr::width(line_so_far.as_str()) + UnicodeWidthStr::width(flag.as_str()) + 4 > settings.columns {
-            println!("{line_so_far}, ");
+            outln!("{}, ", settings.shell.escape_double_quoted(line_so_far));
             line_so_far = format!("       {flag}");
           } else {
             line_so_far.push_str(", ");
             line_so_far.push_str(flag);
           }
         }
-        println!("{line_so_far}");
+        line_so_far.push_str(&help_flag_tags(arg));
+        outln!("{}", settings.shell.escape_double_quoted(line_so_far));
 
         for detail in arg.get_help_details() {
           match detail {
             argument::HelpDetailSection::Text(text) => {
-                println!("{}\n", cleanup_help_text(&Some(text), &deep_options));
+                outln!("{}\n", cleanup_help_text(settings, &Some(text), &deep_options));
               },
             argument::HelpDetailSection::ListItem(text) => {
-                println!("{}\n", cleanup_help_text(&Some(text), &list_options));
+                outln!("{}\n", cleanup_help_text(settings, &Some(text), &list_options));
               },
           }
         }
 
+        for line in constraint_help_lines(settings, arg) {
+          outln!("{}\n", cleanup_help_text(settings, &Some(line), &deep_options));
+        }
+
+        match arg.get_help_default() {
+          None => {},
+          Some(text) => { outln!("{}\n", cleanup_help_text(settings, &Some(text), &deep_options)); }
+        }
+      }
+    }
+  }
+
+  match settings.shell {
+    Shell::PowerShell => outln!("\"@"),
+    _ => outln!("\""),
+  }
+
+  match settings.shell {
+    Shell::Posix | Shell::Zsh | Shell::Fish if settings.no_pager => {
+      outln!("printf '%s\\n' \"$HELP_TEXT\"");
+      outln!("{}", if settings.shell == Shell::Fish { "end" } else { ")" });
+    }
+    Shell::Posix => {
+      outln!("echo \"$HELP_TEXT\" | $HELP_PAGER");
+      outln!(")");
+    }
+    Shell::Zsh => {
+      // Zsh doesn't split unquoted variables into words, so `${=...}` is needed for a pager with
+      // arguments. Its `echo` also treats backslashes as escapes, which `printf` doesn't.
+      outln!("printf '%s\\n' \"$HELP_TEXT\" | ${{=HELP_PAGER}}");
+      outln!(")");
+    }
+    Shell::Fish => {
+      outln!("echo \"$HELP_TEXT\" | eval $HELP_PAGER");
+      outln!("end");
+    }
+    Shell::PowerShell => {
+      outln!("Write-Output $HELP_TEXT");
+      outln!("}}");
+    }
+  }
+}
+
+/// Writes the help text as a Markdown document, echoed as-is rather than sent through a pager.
+#[allow(clippy::unnecessary_unwrap)]
+fn print_help_markdown(settings: &Settings, show_secret: bool) {
+  let mut doc = String::from("");
+
+  let program_name = match (&settings.program_name, &settings.program_version) {
+    (Some(name), Some(version)) => Some(format!("{name} {version}")),
+    (name, _) => name.clone(),
+  };
+
+  if program_name.is_some() {
+    doc.push_str(&format!("# {}\n\n", program_name.unwrap()));
+  }
+
+  if settings.program_summary.is_some() {
+    doc.push_str(&format!("{}\n\n", markdown_text(settings.program_summary.as_ref().unwrap())));
+  }
+
+  if settings.program_description.is_some() {
+    doc.push_str("## Description\n\n");
+    doc.push_str(&format!("{}\n\n", markdown_text(settings.program_description.as_ref().unwrap())));
+  }
+
+  for (title, section) in help_sections(settings) {
+    doc.push_str(&format!("## {}\n\n", title.unwrap_or(String::from("Options"))));
+
+    for arg in section {
+      if !arg.is_secret() || show_secret {
+        let flags = arg.get_help_flags().iter()
+            .map(|flag| format!("`{flag}`"))
+            .collect::<Vec<String>>();
+        doc.push_str(&format!("### {}{}\n\n", flags.join(", "), help_flag_tags(arg)));
+
+        let mut in_list = false;
+        for detail in arg.get_help_details() {
+          match detail {
+            argument::HelpDetailSection::Text(text) => {
+              if in_list {
+                doc.push('\n');
+                in_list = false;
+              }
+              doc.push_str(&format!("{}\n\n", markdown_text(&text)));
+            },
+            argument::HelpDetailSection::ListItem(text) => {
+              // Every line after the first is indented, so that later paragraphs stay in the item.
+              let item = markdown_text(&text)
+                  .lines()
+                  .map(|line| if line.is_empty() { String::from("") } else { format!("  {line}") })
+                  .collect::<Vec<String>>()
+                  .join("\n");
+              doc.push_str(&format!("- {}\n", item.trim_start()));
+              in_list = true;
+            },
+          }
+        }
+
+        if in_list {
+          doc.push('\n');
+        }
+
+        for line in constraint_help_lines(settings, arg) {
+          doc.push_str(&format!("{line}\n\n"));
+        }
+
+        match arg.get_help_default() {
+          None => {},
+          Some(text) => { doc.push_str(&format!("{}\n\n", markdown_text(&text))); }
+        }
+      }
+    }
+  }
+
+  outln!("{}", settings.shell.print_text(doc.trim_end()));
+}
+
+/// Joins help text lines for Markdown, where a line break within a paragraph needs a trailing
+/// backslash.
+#[allow(clippy::needless_return)]
+fn markdown_text(text: &str) -> String {
+  return join_help_lines(text)
+      .split("\n\n")
+      .map(|paragraph| paragraph.replace('\n', "\\\n"))
+      .collect::<Vec<String>>()
+      .join("\n\n");
+}
+
+/// Escapes text for roff, so that backslashes, dashes, and leading control characters are literal.
+#[allow(clippy::needless_return)]
+fn roff_escape(text: &str) -> String {
+  let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+
+  return escaped.lines()
+      .map(|line| if line.starts_with('.') || line.starts_with('\'') { format!("\\&{line}") } else { line.to_string() })
+      .collect::<Vec<String>>()
+      .join("\n");
+}
+
+/// Writes help text paragraphs as roff, separating paragraphs with `.PP` and keeping line breaks
+/// within a paragraph with `.br`.
+#[allow(clippy::needless_return)]
+fn roff_paragraphs(text: &str) -> String {
+  return join_help_lines(text)
+      .split("\n\n")
+      .map(|paragraph| roff_escape(paragraph).replace('\n', "\n.br\n"))
+      .collect::<Vec<String>>()
+      .join("\n.PP\n");
+}
+
+/// Writes the help text as a man(7) page, echoed as-is rather than sent through a pager.
+#[allow(clippy::unnecessary_unwrap)]
+fn print_help_man(settings: &Settings, show_secret: bool) {
+  let name = settings.program_name.clone().unwrap_or(String::from("command"));
+  let mut doc = String::from("");
+
+  doc.push_str(&format!(
+      ".TH \"{}\" 1 \"\" \"{}\"\n",
+      roff_escape(&name.to_uppercase()),
+      roff_escape(settings.program_version.as_deref().unwrap_or(""))));
+
+  doc.push_str(".SH NAME\n");
+  match &settings.program_summary {
+    None => doc.push_str(&format!("{}\n", roff_escape(&name))),
+    Some(summary) => doc.push_str(&format!("{} \\- {}\n", roff_escape(&name), roff_escape(&join_help_lines(summary)))),
+  }
+
+  doc.push_str(".SH SYNOPSIS\n");
+  doc.push_str(&format!(".B {}\n", roff_escape(&name)));
+  if !settings.arguments.is_empty() {
+    doc.push_str("[\\fIOPTIONS\\fR]\n");
+  }
+
+  if settings.program_description.is_some() {
+    doc.push_str(".SH DESCRIPTION\n");
+    doc.push_str(&format!("{}\n", roff_paragraphs(settings.program_description.as_ref().unwrap())));
+  }
+
+  for (title, section) in help_sections(settings) {
+    doc.push_str(&format!(".SH {}\n", roff_escape(&title.unwrap_or(String::from("OPTIONS")))));
+
+    for arg in section {
+      if !arg.is_secret() || show_secret {
+        doc.push_str(".TP\n");
+        doc.push_str(&format!(
+            "\\fB{}\\fR{}\n",
+            arg.get_help_flags().iter().map(|flag| roff_escape(flag)).collect::<Vec<String>>().join(", "),
+            roff_escape(&help_flag_tags(arg))));
+
+        let mut first = true;
+        for detail in arg.get_help_details() {
+          if !first {
+            doc.push_str(".IP\n");
+          }
+          first = false;
+
+          match detail {
+            argument::HelpDetailSection::Text(text) => {
+              doc.push_str(&format!("{}\n", roff_paragraphs(&text).replace("\n.PP\n", "\n.IP\n")));
+            },
+            argument::HelpDetailSection::ListItem(text) => {
+              doc.push_str(&format!("\\(bu {}\n", roff_paragraphs(&text).replace("\n.PP\n", "\n.IP\n")));
+            },
+          }
+        }
+
+        for line in constraint_help_lines(settings, arg) {
+          doc.push_str(&format!(".IP\n{}\n", roff_escape(&line)));
+        }
+
         match arg.get_help_default() {
           None => {},
-          Some(text) => { println!("{}\n", cleanup_help_text(&Some(text), &deep_options)); }
+          Some(text) => { doc.push_str(&format!(".IP\n{}\n", roff_escape(&join_help_lines(&text)))); }
         }
       }
     }
   }
 
-  println!("\"");
-  println!("echo \"$HELP_TEXT\" | $HELP_PAGER");
-  println!(")");
+  outln!("{}", settings.shell.print_text(doc.trim_end()));
+}
+
+fn print_version(settings: &Settings) {
+  let version = settings.program_version.clone().unwrap();
+
+  match &settings.program_name {
+    None => echo(settings, version),
+    Some(name) => echo(settings, format!("{name} {version}")),
+  }
 }
 
 fn print_help_function(settings: &Settings) {
-  println!("{} () {{", settings.help_function.clone().unwrap());
+  match settings.shell {
+    Shell::Posix | Shell::Zsh => outln!("{} () {{", settings.help_function.clone().unwrap()),
+    Shell::Fish => outln!("function {}", settings.help_function.clone().unwrap()),
+    Shell::PowerShell => outln!("function {} {{", settings.help_function.clone().unwrap()),
+  }
 
-  print_help_text(settings);
+  print_help_text(settings, false);
 
-  println!("}}");
+  match settings.shell {
+    Shell::Posix | Shell::Zsh | Shell::PowerShell => outln!("}}"),
+    Shell::Fish => outln!("end"),
+  }
+}
+
+#[allow(clippy::needless_return)]
+fn print_completion(settings: &Settings) -> Result<(), ArgError> {
+  let program_name = settings.program_name.clone()
+      .ok_or_error(DEFINITION_ERROR, String::from("--program-name must be provided to generate completion"))?;
+  let function_name = format!("_{}_completion", Regex::new(r"[^a-zA-Z0-9_]").unwrap().replace_all(&program_name, "_"));
+
+  let mut all_flags = Vec::new();
+
+  outln!("{function_name} () {{");
+  outln!("  local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"");
+  outln!("  local prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"");
+  outln!("  case \"$prev\" in");
+
+  for arg in settings.arguments.iter() {
+    if !arg.is_secret() {
+      let flags = arg.get_common().get_all_flags();
+      let values = arg.get_completion_values();
+
+      if !flags.is_empty() && !values.is_empty() {
+        outln!("    {})", flags.iter().map(|flag| Shell::Posix.quote(flag)).collect::<Vec<String>>().join("|"));
+        outln!("      COMPREPLY=($(compgen -W {} -- \"$cur\"))", Shell::Posix.quote(values.join(" ")));
+        outln!("      return");
+        outln!("      ;;");
+      }
+
+      all_flags.extend(flags.iter().cloned());
+    }
+  }
+
+  outln!("  esac");
+  outln!("  COMPREPLY=($(compgen -W {} -- \"$cur\"))", Shell::Posix.quote(all_flags.join(" ")));
+  outln!("}}");
+  outln!("complete -F {function_name} {}", Shell::Posix.quote(program_name));
+  return Ok(());
+}
+
+/// Writes the argument definitions as JSON, for `--export-spec`.
+fn export_spec_json(settings: &Settings) {
+  outln!("{{");
+  outln!("  \"program_name\": {},", json::json_optional_string(&settings.program_name));
+  outln!("  \"program_version\": {},", json::json_optional_string(&settings.program_version));
+  outln!("  \"program_summary\": {},", json::json_optional_string(&settings.program_summary));
+  outln!("  \"program_description\": {},", json::json_optional_string(&settings.program_description));
+  outln!("  \"arguments\": [");
+
+  for (i, arg) in settings.arguments.iter().enumerate() {
+    outln!("    {}{}", arg.to_spec_json(), if i + 1 < settings.arguments.len() { "," } else { "" });
+  }
+
+  outln!("  ]");
+  outln!("}}");
+}
+
+/// Writes each argument definition as plain text, for `--print-spec`: its name, its debug info, and
+/// the constraints that the help text would list.
+fn print_spec(settings: &Settings) {
+  for (i, arg) in settings.arguments.iter().enumerate() {
+    if i > 0 {
+      outln!();
+    }
+
+    outln!("{}", arg.get_name());
+    outln!("  {}", arg.get_debug_info());
+    for line in constraint_help_lines(settings, arg.as_ref()) {
+      outln!("  {line}");
+    }
+  }
+}
+
+fn echo<S: AsRef<str>>(settings: &Settings, text: S) {
+  outln!("echo {}", settings.shell.quote(text));
+}
+
+/// Returns a value as it should appear in debug output; values of secret arguments are masked.
+#[allow(clippy::needless_return)]
+fn debug_value<V: std::fmt::Display>(secret: bool, value: V) -> String {
+  if secret {
+    return String::from("****");
+  }
+
+  return value.to_string();
+}
+
+/// Quotes each value for debug output, hiding secret ones.
+fn debug_values(secret: bool, values: &[String]) -> String {
+  values.iter().map(|value| format!("'{}'", debug_value(secret, value))).collect::<Vec<String>>().join(", ")
+}
+
+#[cfg(test)]
+thread_local! {
+  /// Output collected by tests instead of being written to stdout. Each test runs on its own thread,
+  /// so each one only sees its own output.
+  static CAPTURED_OUTPUT: std::cell::RefCell<Option<String>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Writes output to stdout, or to the capture started by a test.
+fn write_output(text: String) {
+  #[cfg(test)]
+  if CAPTURED_OUTPUT.with_borrow(|captured| captured.is_some()) {
+    CAPTURED_OUTPUT.with_borrow_mut(|captured| captured.as_mut().unwrap().push_str(&text));
+    return;
+  }
+
+  print!("{text}");
 }
 
-fn echo<S: AsRef<str>>(text: S) {
-  println!("echo \"{}\"", text.as_ref());
+/// Starts collecting output instead of writing it, until `take_captured_output` is called.
+#[cfg(test)]
+fn capture_output() {
+  CAPTURED_OUTPUT.set(Some(String::new()));
+}
+
+#[cfg(test)]
+fn take_captured_output() -> String {
+  CAPTURED_OUTPUT.take().unwrap_or_default()
 }
 
 fn output_debug<S: AsRef<str>>(settings: &Settings, text: S) {
-  if settings.debug {
-    echo(format!("[ArgParse-sh] {}", text.as_ref()));
+  if settings.debug && settings.output_format == "nul" {
+    eprintln!("[ArgParse-sh] {}", text.as_ref());
+  } else if settings.debug {
+    echo(settings, format!("[ArgParse-sh] {}", text.as_ref()));
   }
 }
 
-fn output_argument<V: std::fmt::Display>(settings: &Settings, name: &String, value: V) {
+#[allow(clippy::unnecessary_unwrap)]
+fn output_argument<V: std::fmt::Display>(settings: &Settings, name: &String, value: V, secret: bool) {
   output_debug(settings, format!(
-      "Setting {}{name} = \\\"{value}\\\"",
-      settings.prefix.clone().unwrap_or(String::from(""))));
+      "Setting {}{name} = \"{}\"",
+      settings.prefix.clone().unwrap_or(String::from("")),
+      debug_value(secret, &value)));
+
+  if settings.output_format == "nul" {
+    write_output(format!("{}{name}\0{value}\0", settings.prefix.clone().unwrap_or(String::from(""))));
+    return;
+  }
+
+  if settings.output_array.is_some() {
+    outln!("{}[{}]={}",
+        settings.output_array.as_ref().unwrap(),
+        settings.shell.quote(format!("{}{name}", settings.prefix.clone().unwrap_or(String::from("")))),
+        settings.shell.quote(value.to_string()));
+    return;
+  }
 
-  println!("{}{}{name}=\"{value}\"",
-      if settings.export { "export " } else { "" },
-      settings.prefix.clone().unwrap_or(String::from("")));
+  outln!("{}", settings.shell.assignment(
+      &format!("{}{name}", settings.prefix.clone().unwrap_or(String::from(""))),
+      &value.to_string(),
+      settings.scope));
 }
 
-pub fn handle_all_arguments(args: Vec<String>) {
-  let settings = parse_settings(args);
+/// Writes values as a count followed by one variable per value, with a 0-based index suffix. With
+/// `--pad-indices` the indices are zero-padded to the width of the largest one.
+fn output_indexed(settings: &Settings, name: &String, values: &[String], secret: bool) {
+  let width = if settings.pad_indices { values.len().saturating_sub(1).to_string().len() } else { 0 };
+
+  output_argument(settings, name, values.len(), false);
+  for (i, value) in values.iter().enumerate() {
+    output_argument(settings, &format!("{name}_{i:0width$}"), value, secret);
+  }
+}
+
+fn output_array(settings: &Settings, name: &String, values: &[String], secret: bool) {
+  // NUL records and associative arrays have no way to express an array, so they fall back to the
+  // indexed form.
+  if settings.output_format == "nul" || settings.output_array.is_some() {
+    output_indexed(settings, name, values, secret);
+    return;
+  }
+
+  output_debug(settings, format!(
+      "Setting {}{name} = ({})",
+      settings.prefix.clone().unwrap_or(String::from("")),
+      values.iter().map(|value| format!("\"{}\"", debug_value(secret, value))).collect::<Vec<String>>().join(" ")));
+
+  outln!("{}", settings.shell.list_assignment(
+      &format!("{}{name}", settings.prefix.clone().unwrap_or(String::from(""))),
+      values,
+      settings.scope));
+}
+
+/// Parses the user's arguments against the argument definitions without writing any shell code. The
+/// definitions are the same as the ones given to the command line tool before `--`, and the input is
+/// what comes after it.
+///
+/// The values are returned by variable name, without any `--prefix`. Arguments that got no value and
+/// have no default are left out. Values are converted the same way as for shell output, whether they
+/// come from the command line, the environment or the default. A choice with `--emit-raw` has both
+/// its option and the raw value, a count has its total, and a list has its items.
+///
+/// Each call starts from the defaults and keeps its settings to itself, so nothing carries over from
+/// an earlier call, and calls on different threads don't affect each other.
+#[allow(clippy::needless_return)]
+pub fn parse(definition: &[String], input: &[String]) -> Result<HashMap<String, Vec<String>>, ArgError> {
+  let mut args = vec![String::from("argparse-sh")];
+  args.extend(definition.iter().cloned());
+  args.push(String::from("--"));
+  args.extend(input.iter().cloned());
+
+  let settings = parse_settings(args, &mut ErrorFormat::default())?;
+
+  let help_flags = [(settings.auto_help, "--help"), (settings.auto_help, "--help-all"), (settings.auto_version, "--version")];
+  if help_flags.iter().any(|(enabled, flag)| *enabled && settings.remaining_args == [*flag]) {
+    return Err(ArgError::Help);
+  }
+
+  let values = parse_argument_values(&settings)?;
+  let sources = resolve_value_sources(&settings, &values)?;
+  validate_argument_values(&settings, &values, &sources)?;
+
+  let mut result = HashMap::new();
+  for (argument, source) in settings.arguments.iter().zip(sources) {
+    let values = match source {
+      ValueSource::CommandLine(values) => values.clone(),
+      ValueSource::Environment(values) => values,
+      ValueSource::Default(default) => vec![argument.default_output_value(&settings, default)],
+      ValueSource::Missing => continue,
+    };
+    result.extend(argument.output_variables(&settings, &values));
+  }
+
+  if settings.rest_string.is_some() {
+    let name = settings.rest_string.clone().unwrap();
+    let rest = values.get(&name).cloned().unwrap_or_default();
+    result.insert(name, rest);
+  }
+  return Ok(result);
+}
+
+/// Runs the command line tool: parses the definitions and the user's arguments, and writes the shell
+/// code that sets the variables. Errors are returned rather than written, so that the caller can pass
+/// them to `write_error` along with `error_format`, which is filled in from the definitions. Help and
+/// version text has already been written when `ArgError::Help` is returned.
+#[allow(clippy::needless_return)]
+pub fn handle_all_arguments(args: Vec<String>, error_format: &mut ErrorFormat) -> Result<(), ArgError> {
+  let settings = parse_settings(args, error_format)?;
 
   debug_setup(&settings);
 
-  if settings.auto_help && settings.remaining_args.len() == 1 && settings.remaining_args.get(0) == Some(&String::from("--help")) {
-    print_help_text(&settings);
-    println!("( exit {HELP_ERROR} )");
-    std::process::exit(HELP_ERROR);
+  if settings.export_spec {
+    export_spec_json(&settings);
+
+  } else if settings.print_spec {
+    print_spec(&settings);
+
+  } else if settings.completion.is_some() {
+    print_completion(&settings)?;
+
+  } else if settings.auto_help && settings.remaining_args.len() == 1 && settings.remaining_args.first() == Some(&String::from("--help")) {
+    print_help_text(&settings, false);
+    return Err(ArgError::Help);
+
+  } else if settings.auto_help && settings.remaining_args.len() == 1 && settings.remaining_args.first() == Some(&String::from("--help-all")) {
+    print_help_text(&settings, true);
+    return Err(ArgError::Help);
+
+  } else if settings.auto_version && settings.remaining_args.len() == 1 && settings.remaining_args.first() == Some(&String::from("--version")) {
+    print_version(&settings);
+    return Err(ArgError::Help);
 
   } else {
-    let values = parse_argument_values(&settings);
+    let values = parse_argument_values(&settings)?;
+
+    let sources = resolve_value_sources(&settings, &values)?;
 
-    validate_argument_values(&settings, &values);
-    output_argument_settings(&settings, &values);
+    validate_argument_values(&settings, &values, &sources)?;
+
+    if settings.validate_only {
+      output_debug(&settings, "");
+      output_debug(&settings, "ArgParse-sh validated the arguments; no values were written");
+      return Ok(());
+    }
+
+    output_argument_settings(&settings, &values, &sources);
 
     if settings.help_function.is_some() {
       print_help_function(&settings);
     }
   }
+
+  return Ok(());
 }
 
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::test_support::eval_in;
+  use super::test_support::run;
+  use super::test_support::strings;
+
+  #[test]
+  fn parse_does_not_keep_settings_from_an_earlier_call() {
+    let path = std::env::temp_dir().join(format!("argparse-sh-messages-{}.txt", std::process::id()));
+    std::fs::write(&path, "invalid_integer=Keine Zahl: {value}\n").unwrap();
+
+    let first = parse(
+        &strings(&["--messages-file", path.to_str().unwrap(), "--shell", "fish", "--error-format", "json", "--integer", "n"]),
+        &strings(&["--n", "x"]));
+    assert_eq!(first, Err(ArgError::User(String::from("Keine Zahl: x"))));
+
+    let second = parse(&strings(&["--integer", "n"]), &strings(&["--n", "x"]));
+    assert_eq!(second, Err(ArgError::User(String::from("Non-integer value 'x' provided for argument N"))));
+
+    std::fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn parse_can_be_called_from_several_threads_at_once() {
+    let path = std::env::temp_dir().join(format!("argparse-sh-threads-{}.txt", std::process::id()));
+    std::fs::write(&path, "invalid_integer=Keine Zahl: {value}\n").unwrap();
+    let translated = strings(&["--messages-file", path.to_str().unwrap(), "--shell", "fish", "--integer", "n"]);
+    let plain = strings(&["--integer", "n"]);
+
+    std::thread::scope(|scope| {
+      for i in 0..8 {
+        let definition = if i % 2 == 0 { &translated } else { &plain };
+        let expected = if i % 2 == 0 { "Keine Zahl: x" } else { "Non-integer value 'x' provided for argument N" };
+        scope.spawn(move || {
+          for _ in 0..50 {
+            assert_eq!(parse(definition, &strings(&["--n", "x"])), Err(ArgError::User(String::from(expected))));
+          }
+        });
+      }
+    });
+
+    std::fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn hostile_values_are_written_safely() {
+    for value in ["he said \"$(rm -rf /)\"", "`echo injected`", "it's", "back\\slash", "$HOME", "a\nb"] {
+      let (output, exit_code) = run(&["--string", "msg", "--", "--msg", value]);
+      assert_eq!(exit_code, 0);
+      assert_eq!(eval_in("sh", &output, "printf '%s' \"$MSG\""), value);
+    }
+  }
+
+  #[test]
+  fn secrets_are_masked_in_debug_output() {
+    let (output, exit_code) = run(&["--debug", "--string", "token", "--secret", "--", "--token", "hunter2"]);
+    assert_eq!(exit_code, 0);
+    let debug: Vec<&str> = output.lines().filter(|line| line.contains("[ArgParse-sh]")).collect();
+    assert!(debug.iter().any(|line| line.contains("TOKEN") && line.contains("****")));
+    assert!(debug.iter().all(|line| !line.contains("hunter2")));
+    let printed = eval_in("sh", &output, "printf '%s' \"$TOKEN\"");
+    assert!(printed.contains("Setting TOKEN = \"****\""));
+    assert!(printed.ends_with("completed successfully\nhunter2"));
+    assert_eq!(printed.matches("hunter2").count(), 1);
+  }
+
+  #[test]
+  fn required_catch_all_needs_at_least_one_value() {
+    let definition = ["--string", "files", "--catch-all", "--repeated", "--required"];
+
+    let none = parse(&strings(&definition), &[]);
+    assert_eq!(none, Err(ArgError::User(String::from("At least one value must be provided for catch-all argument FILES"))));
+
+    let one = parse(&strings(&definition), &strings(&["a"])).unwrap();
+    assert_eq!(one["FILES"], strings(&["a"]));
+
+    let many = parse(&strings(&definition), &strings(&["a", "b", "c"])).unwrap();
+    assert_eq!(many["FILES"], strings(&["a", "b", "c"]));
+
+    let (output, exit_code) = run(&["--string", "files", "--catch-all", "--repeated", "--required", "--min-count", "2", "--", "a"]);
+    assert_eq!(exit_code, USER_ERROR);
+    assert!(!output.contains("At least one value"));
+  }
+
+  #[test]
+  #[allow(clippy::needless_return, clippy::unnecessary_unwrap)]
+  fn command_line_beats_environment_beats_default() {
+    let set = "ARGPARSE_SH_TEST_PRECEDENCE_SET";
+    let unset = "ARGPARSE_SH_TEST_PRECEDENCE_UNSET";
+    std::env::set_var(set, "env");
+    std::env::remove_var(unset);
+
+    let value = |env: &str, default: Option<&str>, input: &[&str]| {
+      let mut definition = vec!["--string", "n", "--required", "--env", env];
+      if default.is_some() {
+        definition.extend(["--default", default.unwrap()]);
+      }
+      return parse(&strings(&definition), &strings(input)).map(|values| values["N"].clone());
+    };
+
+    assert_eq!(value(set, Some("default"), &["--n", "cli"]), Ok(strings(&["cli"])));
+    assert_eq!(value(unset, Some("default"), &["--n", "cli"]), Ok(strings(&["cli"])));
+    assert_eq!(value(set, None, &["--n", "cli"]), Ok(strings(&["cli"])));
+    assert_eq!(value(set, Some("default"), &[]), Ok(strings(&["env"])));
+    assert_eq!(value(set, None, &[]), Ok(strings(&["env"])));
+    assert_eq!(value(unset, Some("default"), &[]), Ok(strings(&["default"])));
+    assert_eq!(value(unset, None, &[]), Err(ArgError::User(String::from("Value for argument N is missing"))));
+
+    std::env::remove_var(set);
+  }
+
+  #[test]
+  fn negative_numbers_are_read_as_values() {
+    let definition = strings(&["--integer", "count", "--flag", "-c", "--float", "scale", "--flag", "-s"]);
+
+    for input in [
+        vec!["--count", "-5", "--scale", "-2.5"],
+        vec!["--count=-5", "--scale=-2.5"],
+        vec!["-c", "-5", "-s", "-2.5"],
+        vec!["-c=-5", "-s=-2.5"]] {
+      let values = parse(&definition, &strings(&input)).unwrap();
+      assert_eq!(values["COUNT"], strings(&["-5"]));
+      assert_eq!(values["SCALE"], strings(&["-2.5"]));
+    }
+
+    let values = parse(&definition, &strings(&["--count", "-0x10", "--scale", "-1e3"])).unwrap();
+    assert_eq!(values["COUNT"], strings(&["-16"]));
+    assert_eq!(values["SCALE"], strings(&["-1000"]));
+  }
+
+  #[test]
+  fn typos_suggest_a_close_flag() {
+    let definition = strings(&["--boolean", "verbose", "--flag", "-v", "--string", "name", "--flag", "-n"]);
+    let extra = |value: &str| ArgError::User(format!("Extra argument \"{value}\" passed and no catch-all argument found"));
+
+    assert_eq!(
+        parse(&definition, &strings(&["--verbos"])),
+        Err(ArgError::User(String::from("Extra argument \"--verbos\" passed and no catch-all argument found. Did you mean --verbose?"))));
+    assert_eq!(
+        parse(&definition, &strings(&["--nmae=x"])),
+        Err(ArgError::User(String::from("Extra argument \"--nmae=x\" passed and no catch-all argument found. Did you mean --name?"))));
+    for token in ["-5", "-1.5", "-1e3", "-x", "--zzzzzzzz"] {
+      assert_eq!(parse(&definition, &strings(&[token])), Err(extra(token)));
+    }
+  }
+
+  #[test]
+  fn empty_catch_all_gets_its_default() {
+    assert_eq!(run(&["--string", "rest", "--catch-all", "--default", "x", "--"]).0, "REST='x'\n");
+    assert_eq!(run(&["--string", "rest", "--catch-all", "--repeated", "--default", "x", "--"]).0, "REST='1'\nREST_0='x'\n");
+    assert_eq!(run(&["--string", "rest", "--catch-all", "--repeated", "--default", "x", "--", "a"]).0, "REST='1'\nREST_0='a'\n");
+    assert_eq!(run(&["--string", "rest", "--catch-all", "--"]).0, "");
+  }
+
+  #[test]
+  fn ordinals_bind_before_the_catch_all() {
+    let ordinals = ["--string", "src", "--ordinal", "0", "--string", "dst", "--ordinal", "1"];
+    let catch_all = ["--string", "extra", "--catch-all", "--repeated", "--boolean", "verbose"];
+
+    for definition in [[&ordinals[..], &catch_all[..]].concat(), [&catch_all[..], &ordinals[..]].concat()] {
+      for input in [vec!["a", "b", "c", "d", "e"], vec!["a", "--verbose", "b", "c", "d", "e"], vec!["a", "b", "c", "--verbose", "d", "e"]] {
+        let values = parse(&strings(&definition), &strings(&input)).unwrap();
+        assert_eq!(values["SRC"], strings(&["a"]));
+        assert_eq!(values["DST"], strings(&["b"]));
+        assert_eq!(values["EXTRA"], strings(&["c", "d", "e"]));
+      }
+    }
+  }
+
+  #[test]
+  fn skip_unset_writes_nothing_for_missing_arguments() {
+    let definition = ["--skip-unset", "--string", "name", "--string", "tag", "--repeated", "--list", "items", "--string", "mode", "--default", "fast"];
+
+    let (output, exit_code) = run(&[&definition[..], &["--"]].concat());
+    assert_eq!((output.as_str(), exit_code), ("MODE='fast'\n", 0));
+
+    let (output, _) = run(&[&["--debug"], &definition[..], &["--", "--tag", "a"]].concat());
+    assert!(output.lines().all(|line| !line.contains("Setting NAME") && !line.contains("Setting ITEMS")));
+    assert!(output.lines().all(|line| !line.starts_with("NAME") && !line.starts_with("ITEMS")));
+    assert!(output.contains("\nTAG_0='a'\n"));
+
+    let (output, _) = run(&["--string", "name", "--string", "tag", "--repeated", "--"]);
+    assert_eq!(output, "TAG='0'\n");
+  }
+
+  #[test]
+  fn max_width_has_a_minimum() {
+    for width in ["0", "1", "19"] {
+      assert_eq!(
+          parse(&strings(&["--max-width", width]), &[]),
+          Err(ArgError::Definition(format!("Value '{width}' provided for --max-width must be at least 20"))));
+    }
+    assert!(parse(&strings(&["--max-width", "20"]), &[]).is_ok());
+  }
+
+  #[test]
+  fn echoed_text_is_not_expanded() {
+    let (output, _) = run(&["--auto-version", "--program-version", "$(echo injected) `x`", "--", "--version"]);
+    assert_eq!(eval_in("sh", &output, ""), "$(echo injected) `x`\n");
+  }
+}
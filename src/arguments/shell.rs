@@ -0,0 +1,257 @@
+/// Where the variables that are written can be seen.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Scope {
+  /// Plain variables in the current scope.
+  Plain,
+  /// Variables exported to child processes, with `--export`.
+  Export,
+  /// Variables local to the calling function, with `--local`.
+  Local,
+}
+
+/// The shell that the generated output is written for.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[allow(clippy::enum_variant_names)]
+pub enum Shell {
+  #[default]
+  Posix,
+  Zsh,
+  Fish,
+  PowerShell,
+}
+
+impl Shell {
+  pub fn from_name(name: &str) -> Option<Shell> {
+    match name {
+      "posix" | "sh" | "bash" => Some(Shell::Posix),
+      "zsh" => Some(Shell::Zsh),
+      "fish" => Some(Shell::Fish),
+      "powershell" | "pwsh" => Some(Shell::PowerShell),
+      _ => None,
+    }
+  }
+
+  pub fn get_name(&self) -> &'static str {
+    match self {
+      Shell::Posix => "posix",
+      Shell::Zsh => "zsh",
+      Shell::Fish => "fish",
+      Shell::PowerShell => "powershell",
+    }
+  }
+
+  /// Whether repeated values are written as a single native list rather than indexed variables.
+  pub fn has_native_lists(&self) -> bool {
+    match self {
+      Shell::Posix => false,
+      Shell::Zsh | Shell::Fish | Shell::PowerShell => true,
+    }
+  }
+
+  /// Quotes a value so that the shell reads it back verbatim, with no expansion of any kind.
+  ///
+  /// For POSIX shells and zsh the value is wrapped in single quotes and each embedded single quote
+  /// is written as `'\''`. Fish allows `\'` and `\\` escapes inside single quotes instead, and
+  /// PowerShell doubles each embedded single quote.
+  pub fn quote<S: AsRef<str>>(&self, value: S) -> String {
+    match self {
+      Shell::Posix | Shell::Zsh => format!("'{}'", value.as_ref().replace('\'', "'\\''")),
+      Shell::Fish => format!("'{}'", value.as_ref().replace('\\', "\\\\").replace('\'', "\\'")),
+      Shell::PowerShell => format!("'{}'", value.as_ref().replace('\'', "''")),
+    }
+  }
+
+  /// Escapes text that will be placed inside a double-quoted string. For PowerShell this is an
+  /// expanding here-string, where only backticks and `$` need to be escaped.
+  #[allow(clippy::needless_return)]
+  pub fn escape_double_quoted<S: AsRef<str>>(&self, text: S) -> String {
+    if *self == Shell::PowerShell {
+      return text.as_ref().replace('`', "``").replace('$', "`$");
+    }
+
+    let text = text.as_ref().replace('\\', "\\\\");
+    let text = match self {
+      Shell::Posix | Shell::Zsh => text.replace('`', "\\`"),
+      _ => text,
+    };
+    return text.replace('"', "\\\"").replace('$', "\\$");
+  }
+
+  /// A reference to a variable that can be placed inside a double-quoted string.
+  pub fn variable_in_string(&self, name: &str) -> String {
+    match self {
+      Shell::Posix | Shell::Zsh | Shell::PowerShell => format!("${{{name}}}"),
+      Shell::Fish => format!("\"${name}\""),
+    }
+  }
+
+  /// The text written before a variable name to give it the requested scope.
+  fn scope_prefix(&self, scope: Scope) -> &'static str {
+    match (self, scope) {
+      (_, Scope::Plain) => "",
+      (Shell::Posix | Shell::Zsh, Scope::Export) => "export ",
+      (Shell::Posix | Shell::Zsh, Scope::Local) => "local ",
+      (Shell::Fish, Scope::Export) => "-x ",
+      (Shell::Fish, Scope::Local) => "-l ",
+      (Shell::PowerShell, Scope::Export) => "env:",
+      (Shell::PowerShell, Scope::Local) => "local:",
+    }
+  }
+
+  pub fn assignment(&self, name: &str, value: &str, scope: Scope) -> String {
+    let prefix = self.scope_prefix(scope);
+    match self {
+      Shell::Posix | Shell::Zsh => format!("{prefix}{name}={}", self.quote(value)),
+      Shell::Fish => format!("set {prefix}{name} {}", self.quote(value)),
+      Shell::PowerShell => format!("${prefix}{name} = {}", self.quote(value)),
+    }
+  }
+
+  /// Assigns a list of values as an array. Zsh declares the array with `typeset`, keeping it global
+  /// even when the output is evaluated inside a function. Arrays can't be exported from zsh (or
+  /// bash), so lists are rejected with `--export` for zsh before anything is written.
+  pub fn list_assignment(&self, name: &str, values: &[String], scope: Scope) -> String {
+    let prefix = self.scope_prefix(scope);
+    let quoted = values.iter().map(|value| self.quote(value)).collect::<Vec<String>>();
+    match self {
+      Shell::Posix => format!("{prefix}{name}=({})", quoted.join(" ")),
+      Shell::Zsh if scope == Scope::Local => format!("local -a {name}=({})", quoted.join(" ")),
+      Shell::Zsh => format!("typeset -g -a {name}=({})", quoted.join(" ")),
+      Shell::Fish => format!("set {prefix}{name}{}", quoted.iter().map(|q| format!(" {q}")).collect::<String>()),
+      Shell::PowerShell => format!("${prefix}{name} = @({})", quoted.join(", ")),
+    }
+  }
+
+  /// A command that prints text verbatim; unlike `echo` in some shells, backslashes are left alone.
+  pub fn print_text<S: AsRef<str>>(&self, text: S) -> String {
+    match self {
+      Shell::Posix | Shell::Zsh | Shell::Fish => format!("printf '%s\\n' {}", self.quote(text)),
+      Shell::PowerShell => format!("Write-Output {}", self.quote(text)),
+    }
+  }
+
+  /// A command that leaves the given exit status behind without exiting the calling shell.
+  pub fn exit_status(&self, exit_code: i32) -> String {
+    match self {
+      Shell::Posix | Shell::Zsh => format!("( exit {exit_code} )"),
+      Shell::Fish => format!("sh -c 'exit {exit_code}'"),
+      Shell::PowerShell => format!("$global:LASTEXITCODE = {exit_code}"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use super::super::errors::DEFINITION_ERROR;
+  use super::super::test_support::eval_in;
+  use super::super::test_support::has_shell;
+  use super::super::test_support::run;
+
+  const HOSTILE_VALUES: [&str; 8] = [
+    "he said \"$(rm -rf /)\"",
+    "`touch /tmp/argparse-sh-injected`",
+    "it's",
+    "'; echo injected; '",
+    "back\\slash \\n \\\\",
+    "$HOME ${PATH} $((1+1))",
+    "two\nlines",
+    "",
+  ];
+
+  #[test]
+  fn posix_quoting_gives_back_the_exact_value() {
+    for value in HOSTILE_VALUES {
+      let assignment = Shell::Posix.assignment("VALUE", value, Scope::Plain);
+      assert_eq!(eval_in("sh", &assignment, "printf '%s' \"$VALUE\""), value);
+    }
+  }
+
+  #[test]
+  fn powershell_assignments_for_each_type() {
+    let (output, exit_code) = run(&[
+        "--shell", "powershell",
+        "--string", "s", "--integer", "i", "--uint", "u", "--float", "f", "--bool", "b", "--count", "c",
+        "--list", "l", "--choice", "ch", "--option", "a", "--path", "p", "--bytes", "by", "--duration", "d",
+        "--string", "r", "--repeated",
+        "--",
+        "--s", "it's", "--i", "-3", "--u", "4", "--f", "1.5", "--b", "--c", "--c", "--l", "x,y", "--ch", "a",
+        "--p", "/tmp", "--by", "1K", "--d", "1m", "--r", "a", "--r", "b"]);
+
+    assert_eq!(exit_code, 0);
+    assert_eq!(output, concat!(
+        "$S = 'it''s'\n",
+        "$I = '-3'\n",
+        "$U = '4'\n",
+        "$F = '1.5'\n",
+        "$B = 'true'\n",
+        "$C = '2'\n",
+        "$L = @('x', 'y')\n",
+        "$CH = 'a'\n",
+        "$P = '/tmp'\n",
+        "$BY = '1024'\n",
+        "$D = '60'\n",
+        "$R = @('a', 'b')\n"));
+  }
+
+  #[test]
+  fn powershell_scopes_and_errors() {
+    assert_eq!(run(&["--shell", "powershell", "--export", "--string", "s", "--", "--s", "v"]), (String::from("$env:S = 'v'\n"), 0));
+    assert_eq!(
+        run(&["--shell", "powershell", "--export", "--string", "s", "--repeated", "--join", ",", "--", "--s", "a", "--s", "b"]),
+        (String::from("$env:S = 'a,b'\n"), 0));
+    assert_eq!(run(&["--shell", "powershell", "--export", "--string", "s", "--repeated", "--", "--s", "v"]).1, DEFINITION_ERROR);
+    assert_eq!(run(&["--shell", "powershell", "--export", "--list", "l", "--", "--l", "a,b"]).1, DEFINITION_ERROR);
+    assert_eq!(run(&["--shell", "powershell", "--export", "--emit-provided", "given", "--string", "s", "--"]).1, DEFINITION_ERROR);
+    assert_eq!(run(&["--shell", "powershell", "--local", "--string", "s", "--", "--s", "v"]), (String::from("$local:S = 'v'\n"), 0));
+    assert_eq!(run(&["--shell", "powershell", "--integer", "i", "--", "--i", "x"]), (String::from(concat!(
+        "echo \"\"\n",
+        "echo '!!! ArgParse-sh Error: Non-integer value ''x'' provided for argument I !!!'\n",
+        "echo \"\"\n",
+        "$global:LASTEXITCODE = 3\n")), 3));
+  }
+
+  #[test]
+  fn printed_text_is_not_interpreted() {
+    for value in HOSTILE_VALUES {
+      assert_eq!(eval_in("sh", &Shell::Posix.print_text(value), ""), format!("{value}\n"));
+    }
+  }
+
+  #[test]
+  fn zsh_writes_native_arrays() {
+    assert_eq!(
+        run(&["--shell", "zsh", "--string", "tag", "--repeated", "--list", "items", "--", "--tag", "a b", "--tag", "it's", "--items", "x,y"]).0,
+        "typeset -g -a TAG=('a b' 'it'\\''s')\ntypeset -g -a ITEMS=('x' 'y')\n");
+    assert_eq!(run(&["--shell", "zsh", "--string", "tag", "--repeated", "--"]).0, "typeset -g -a TAG=()\n");
+    assert_eq!(run(&["--shell", "zsh", "--local", "--string", "tag", "--repeated", "--", "--tag", "a"]).0, "local -a TAG=('a')\n");
+    assert_eq!(run(&["--shell", "zsh", "--export", "--string", "tag", "--repeated", "--", "--tag", "a"]).1, DEFINITION_ERROR);
+    assert_eq!(
+        run(&["--shell", "zsh", "--export", "--string", "tag", "--repeated", "--join", ",", "--", "--tag", "a", "--tag", "b"]).0,
+        "export TAG='a,b'\n");
+  }
+
+  #[test]
+  fn zsh_arrays_evaluate_in_zsh() {
+    if !has_shell("zsh") {
+      return;
+    }
+
+    let mut args = vec!["--shell", "zsh", "--string", "tag", "--repeated", "--"];
+    for value in HOSTILE_VALUES {
+      args.extend(["--tag", value]);
+    }
+    let (output, exit_code) = run(&args);
+    assert_eq!(exit_code, 0);
+    assert_eq!(eval_in("zsh", &output, "print -r -- ${#TAG}"), format!("{}\n", HOSTILE_VALUES.len()));
+    for (i, value) in HOSTILE_VALUES.iter().enumerate() {
+      assert_eq!(eval_in("zsh", &output, &format!("printf '%s' \"${{TAG[{}]}}\"", i + 1)), *value);
+    }
+
+    let (output, _) = run(&["--shell", "zsh", "--string", "tag", "--repeated", "--"]);
+    assert_eq!(eval_in("zsh", &output, "setopt nounset; print -r -- ${#TAG}"), "0\n");
+
+    let (output, _) = run(&["--shell", "zsh", "--string", "tag", "--repeated", "--", "--tag", "inside"]);
+    assert_eq!(eval_in("zsh", &format!("f() {{\n{output}\n}}\nf"), "print -r -- $TAG[1]"), "inside\n");
+  }
+}
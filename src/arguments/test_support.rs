@@ -0,0 +1,47 @@
+//! Helpers shared by the unit tests of the argument modules.
+
+use std::collections::HashMap;
+
+pub fn strings(values: &[&str]) -> Vec<String> {
+  values.iter().map(|value| value.to_string()).collect()
+}
+
+/// Calls `parse` with string slices.
+#[allow(clippy::needless_return)]
+pub fn parse_values(definition: &[&str], input: &[&str]) -> Result<HashMap<String, Vec<String>>, super::ArgError> {
+  return super::parse(&strings(definition), &strings(input));
+}
+
+/// Runs the command line tool with the given arguments (without the program name), and returns
+/// everything it wrote along with the exit status. Errors are written the way `main` writes them.
+#[allow(clippy::needless_return)]
+pub fn run(args: &[&str]) -> (String, i32) {
+  let mut all_args = vec![String::from("argparse-sh")];
+  all_args.extend(strings(args));
+
+  let mut error_format = super::ErrorFormat::default();
+  super::capture_output();
+  let exit_code = match super::handle_all_arguments(all_args, &mut error_format) {
+    Ok(()) => 0,
+    Err(error) => super::write_error(&error, &error_format),
+  };
+  return (super::take_captured_output(), exit_code);
+}
+
+/// Whether a shell is installed, for tests that can only run where it is.
+#[allow(clippy::needless_return)]
+pub fn has_shell(shell: &str) -> bool {
+  return std::process::Command::new(shell).arg("-c").arg("true").output().is_ok();
+}
+
+/// Evaluates generated output with a shell, followed by `script`, and returns what the script
+/// printed.
+#[allow(clippy::needless_return)]
+pub fn eval_in(shell: &str, output: &str, script: &str) -> String {
+  let result = std::process::Command::new(shell)
+      .arg("-c")
+      .arg(format!("{output}\n{script}"))
+      .output()
+      .unwrap();
+  return String::from_utf8(result.stdout).unwrap();
+}
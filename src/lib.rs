@@ -0,0 +1,12 @@
+//! Structured argument parsing for shell scripts.
+//!
+//! The `argparse-sh` command line tool writes shell code that sets a variable for each argument.
+//! The same parser can be used from Rust with `parse`, which returns the values instead.
+
+mod arguments;
+
+pub use arguments::handle_all_arguments;
+pub use arguments::parse;
+pub use arguments::write_error;
+pub use arguments::ArgError;
+pub use arguments::ErrorFormat;
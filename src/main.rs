@@ -1,8 +1,9 @@
 use std::env;
 
-mod arguments;
-
 fn main() {
-  arguments::handle_all_arguments(env::args().collect());
+  let mut error_format = argparse_sh::ErrorFormat::default();
+  match argparse_sh::handle_all_arguments(env::args().collect(), &mut error_format) {
+    Ok(()) => {}
+    Err(error) => std::process::exit(argparse_sh::write_error(&error, &error_format)),
+  }
 }
-